@@ -0,0 +1,66 @@
+//! Measures the time and number of heap allocations needed to parse a large TAP document.
+//!
+//! Run with `cargo bench --bench allocations`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use tapconsooomer::Document;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const TESTS: usize = 10_000;
+const ITERATIONS: u32 = 10;
+
+fn large_document() -> String {
+    let mut content = format!("TAP version 14\n1..{}\n", TESTS);
+    for i in 1..=TESTS {
+        content += &match i % 4 {
+            0 => format!("NOT OK {} - failing test # TODO not yet implemented\n", i),
+            1 => format!("ok {} - passing test\n", i),
+            2 => format!("ok {} - skipped test # SKIP unsupported platform\n", i),
+            _ => format!("Not Ok {} - another failing test\n", i),
+        };
+    }
+    content
+}
+
+fn main() {
+    let content = large_document();
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let document = Document::parse_from_str(&content).expect("Parser error");
+        assert_eq!(document.body.len(), TESTS);
+    }
+    let elapsed = start.elapsed() / ITERATIONS;
+    let allocations = (ALLOCATIONS.load(Ordering::Relaxed) - allocations) / ITERATIONS as usize;
+
+    println!(
+        "parse {} tests: {:?}/iter, {} allocations/iter ({:.2} per test)",
+        TESTS,
+        elapsed,
+        allocations,
+        allocations as f64 / TESTS as f64
+    );
+}
@@ -0,0 +1,78 @@
+//! Measures parser throughput in tests per second over the documents in `examples/`.
+//!
+//! Run with `cargo bench --bench throughput`.
+//!
+//! Every call to [`Document::parse_from_str`] constructs a fresh pest parser state, including its token queue and
+//! stack. pest offers no way of handing that state, or any other buffer, on to the next call, so allocations can't be
+//! reused across documents. The `parse_generated` group shows what that costs: comparing documents of 1 and 1000
+//! tests yields the fixed overhead per call, next to the cost per test. The `parse_large` group parses a single document
+//! of 50,000 tests, whose throughput should match that of the 1000 test document.
+
+use std::fs;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use tapconsooomer::Document;
+
+const EXAMPLES: [&str; 6] = [
+    "cascading",
+    "common",
+    "metadata",
+    "move_mount_set_group_test",
+    "small",
+    "yaml",
+];
+
+/// A flat document of `tests` tests, as emitted by a typical producer.
+fn generated_document(tests: usize) -> String {
+    let mut content = format!("TAP version 14\n1..{}\n", tests);
+    for i in 1..=tests {
+        content += &format!("ok {} - test {}\n", i, i);
+    }
+    content
+}
+
+fn parse_examples(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for example in EXAMPLES {
+        let content =
+            fs::read_to_string(format!("examples/{}.tap", example)).expect("Failed to read file");
+        let tests = Document::parse_from_str(&content)
+            .expect("Parser error")
+            .tests()
+            .count();
+        group.throughput(Throughput::Elements(tests as u64));
+        group.bench_function(example, |b| {
+            b.iter(|| Document::parse_from_str(&content).expect("Parser error"))
+        });
+    }
+    group.finish();
+}
+
+fn parse_generated(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_generated");
+    for tests in [1, 10, 100, 1000] {
+        let content = generated_document(tests);
+        group.throughput(Throughput::Elements(tests as u64));
+        group.bench_function(tests.to_string(), |b| {
+            b.iter(|| Document::parse_from_str(&content).expect("Parser error"))
+        });
+    }
+    group.finish();
+}
+
+/// A single large document, so parsing time growing faster than the document, e.g. by locating each statement from the
+/// start of the input, shows up as a drop in throughput compared to `parse_generated`.
+fn parse_large(c: &mut Criterion) {
+    const TESTS: usize = 50_000;
+    let mut group = c.benchmark_group("parse_large");
+    group.sample_size(10);
+    let content = generated_document(TESTS);
+    group.throughput(Throughput::Elements(TESTS as u64));
+    group.bench_function(TESTS.to_string(), |b| {
+        b.iter(|| Document::parse_from_str(&content).expect("Parser error"))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse_examples, parse_generated, parse_large);
+criterion_main!(benches);
@@ -0,0 +1,64 @@
+//! Prints a human readable report of a TAP file, showing idiomatic use of the library.
+//!
+//! Run with `cargo run --example report -- examples/cascading.tap`.
+//!
+//! Tests are listed in document order, indented by the depth of the subtests containing them, followed by any bail-out
+//! and the summary. Exits with `1` if any test failed, not counting TODO failures, or the producer bailed out.
+
+use std::{env, error::Error, fs, process};
+
+use tapconsooomer::{Document, Key, Test};
+
+/// A one-word outcome of `test`, honoring its directive.
+fn outcome(test: &Test) -> &'static str {
+    match (test.directive.as_ref().map(|d| d.key), test.result) {
+        (Some(Key::Skip), _) => "skip",
+        (Some(Key::Todo), false) => "todo",
+        (Some(Key::Todo), true) => "fixed",
+        (_, true) => "pass",
+        (_, false) => "FAIL",
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let path = env::args()
+        .nth(1)
+        .ok_or("Usage: report <FILE>, e.g. examples/cascading.tap")?;
+    let content = fs::read_to_string(&path)?;
+    let doc = Document::parse_from_str(&content)?;
+
+    println!(
+        "{}: TAP version {}, planned {}..{}",
+        path, doc.preamble.version, doc.plan.first, doc.plan.last
+    );
+    for (depth, test) in doc.tests_with_depth() {
+        print!(
+            "{}{:5} {}",
+            "  ".repeat(depth),
+            outcome(test),
+            test.effective_number
+        );
+        if let Some(description) = &test.description {
+            print!(" - {}", description);
+        }
+        if let Some(reason) = test.directive.as_ref().and_then(|d| d.reason) {
+            print!(" ({})", reason);
+        }
+        println!();
+    }
+    if let Some(bail_out) = doc.bail_out_nested() {
+        println!(
+            "Bailed out at line {}: {}",
+            bail_out.span.start_line,
+            bail_out.reason.unwrap_or("no reason given")
+        );
+    }
+
+    let summary = doc.summary();
+    println!("{}", summary);
+    let failed = summary.failed > doc.expected_failures().len();
+    if failed || summary.bailed_out {
+        process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,214 @@
+//! Programmatic construction of TAP documents.
+
+use alloc::{borrow::ToOwned, collections::BTreeMap, string::String, vec::Vec};
+
+use crate::{
+    OwnedDirective, OwnedDocument, OwnedKey, OwnedPlan, OwnedPreamble, OwnedStatement,
+    OwnedSubtest, OwnedTest, Span,
+};
+
+/// Builds an [`OwnedDocument`] test by test, e.g. to produce TAP output from a test runner.
+///
+/// Tests are numbered sequentially, starting at `1`. Use [`DocumentBuilder::number`] to override the number of the
+/// most recent test, numbering continues from there. Unless set by [`DocumentBuilder::plan`], the plan is derived from
+/// the number of top-level tests. Built documents carry [`Span::default`] locations throughout.
+///
+/// # Examples
+///
+/// ```
+/// use tapconsooomer::DocumentBuilder;
+///
+/// let doc = DocumentBuilder::new()
+///     .ok("foo()")
+///     .not_ok("bar()")
+///     .skip("no network")
+///     .subtest("baz", |b| b.ok("baz() a").ok("baz() b"))
+///     .build();
+/// assert_eq!(
+///     doc.to_string(),
+///     concat!(
+///         "TAP version 14\n",
+///         "1..4\n",
+///         "ok 1 - foo()\n",
+///         "not ok 2 - bar()\n",
+///         "ok 3 # SKIP no network\n",
+///         "# Subtest: baz\n",
+///         "  1..2\n",
+///         "  ok 1 - baz() a\n",
+///         "  ok 2 - baz() b\n",
+///         "ok 4 - baz\n",
+///     )
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct DocumentBuilder {
+    version: String,
+    plan: Option<(i32, i32)>,
+    body: Vec<OwnedStatement>,
+    next_number: i32,
+}
+
+impl Default for DocumentBuilder {
+    fn default() -> Self {
+        Self {
+            version: "14".to_owned(),
+            plan: None,
+            body: Vec::new(),
+            next_number: 1,
+        }
+    }
+}
+
+impl DocumentBuilder {
+    /// Create a [`DocumentBuilder`] for an empty TAP14 document.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the TAP version declared by the preamble. Defaults to `14`.
+    pub fn version(mut self, version: &str) -> Self {
+        self.version = version.to_owned();
+        self
+    }
+
+    /// Set the plan, overriding the one derived from the number of top-level tests.
+    pub fn plan(mut self, first: i32, last: i32) -> Self {
+        self.plan = Some((first, last));
+        self
+    }
+
+    /// Add a passing test.
+    pub fn ok(self, description: &str) -> Self {
+        self.test(true, Some(description), None)
+    }
+
+    /// Add a failing test.
+    pub fn not_ok(self, description: &str) -> Self {
+        self.test(false, Some(description), None)
+    }
+
+    /// Add a passing test without description, skipped for the given `reason`.
+    pub fn skip(self, reason: &str) -> Self {
+        self.test(true, None, Some(reason))
+    }
+
+    /// Override the number of the most recently added test. Following tests are numbered from `number` onwards. Has
+    /// no effect if no test was added yet.
+    pub fn number(mut self, number: i32) -> Self {
+        if let Some(OwnedStatement::Test(test)) = self
+            .body
+            .iter_mut()
+            .rev()
+            .find(|s| matches!(s, OwnedStatement::Test(_)))
+        {
+            test.number = Some(number);
+            self.next_number = number.saturating_add(1);
+        }
+        self
+    }
+
+    /// Add a subtest named `name`, whose body is built by `build`, followed by the test line closing it. The closing
+    /// test passes if every test within the subtest either passed or is a [`OwnedKey::Todo`].
+    pub fn subtest(self, name: &str, build: impl FnOnce(Self) -> Self) -> Self {
+        let subtest = build(Self::new()).build();
+        let passed = subtest.body.iter().all(|s| match s {
+            OwnedStatement::Test(t) => {
+                t.result || matches!(&t.directive, Some(d) if matches!(d.key, OwnedKey::Todo))
+            }
+            _ => true,
+        });
+        let mut builder = self.test(passed, Some(name), None);
+        let closing = builder.body.pop();
+        builder.body.push(OwnedStatement::Subtest(OwnedSubtest {
+            name: Some(name.to_owned()),
+            raw_declaration: None,
+            plan: subtest.plan,
+            body: subtest.body,
+            span: Span::default(),
+        }));
+        builder.body.extend(closing);
+        builder
+    }
+
+    /// Finish building the [`OwnedDocument`].
+    pub fn build(self) -> OwnedDocument {
+        let tests = self
+            .body
+            .iter()
+            .filter(|s| matches!(s, OwnedStatement::Test(_)))
+            .count();
+        let (first, last) = self.plan.unwrap_or((1, tests as i32));
+        OwnedDocument {
+            preamble: OwnedPreamble {
+                version: self.version,
+            },
+            plan: OwnedPlan::new(first, last),
+            body: self.body,
+            span: Span::default(),
+        }
+    }
+
+    fn test(mut self, result: bool, description: Option<&str>, skip: Option<&str>) -> Self {
+        self.body.push(OwnedStatement::Test(OwnedTest {
+            result,
+            number: Some(self.next_number),
+            raw_number: None,
+            effective_number: self.next_number,
+            description: description.map(str::to_owned),
+            dash: description.is_some(),
+            directive: skip.map(|reason| OwnedDirective {
+                key: OwnedKey::Skip,
+                reason: Some(reason.to_owned()),
+                raw_reason: None,
+            }),
+            metadata: BTreeMap::new(),
+            yaml: Vec::new(),
+            yaml_raw: None,
+            span: Span::default(),
+        }));
+        self.next_number = self.next_number.saturating_add(1);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+    use crate::Document;
+
+    #[test]
+    fn test_builder_round_trip() {
+        let doc = DocumentBuilder::new()
+            .version("13")
+            .plan(1, 5)
+            .ok("first")
+            .number(3)
+            .not_ok("third")
+            .subtest("nested", |b| {
+                b.subtest("deeper", |b| b.not_ok("fails")).ok("passes")
+            })
+            .build();
+        let rendered = doc.to_string();
+        let parsed = Document::parse_from_str(&rendered).expect("Parser error");
+
+        assert_eq!(parsed.preamble.version, "13");
+        assert_eq!((parsed.plan.first, parsed.plan.last), (1, 5));
+        let tests: Vec<_> = parsed
+            .tests_with_depth()
+            .map(|(depth, t)| (depth, t.result, t.number, t.description.as_deref()))
+            .collect();
+        assert_eq!(
+            tests,
+            [
+                (0, true, Some(3), Some("first")),
+                (0, false, Some(4), Some("third")),
+                (2, false, Some(1), Some("fails")),
+                (1, false, Some(1), Some("deeper")),
+                (1, true, Some(2), Some("passes")),
+                (0, false, Some(5), Some("nested")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,301 @@
+//! Error-recovering parsing, reporting every problem of a document instead of just the first.
+
+use alloc::vec::Vec;
+
+use pest::error::{InputLocation, LineColLocation};
+
+use crate::{
+    is_subtest_decl, number_tests, strict_violations, Document, ParseOptions, Plan, Preamble, Span,
+    Statement, TapError,
+};
+
+/// Move the spans of `statement`, parsed from a chunk of the source, to their position within the whole source.
+fn shift(statement: &mut Statement, bytes: usize, lines: usize) {
+    match statement {
+        Statement::Anything(_) | Statement::Comment(_) => {}
+        Statement::BailOut(b) => b.span.shift(bytes, lines),
+        Statement::Pragma(p) => p.span.shift(bytes, lines),
+        Statement::Test(t) => t.span.shift(bytes, lines),
+        Statement::Subtest(s) => {
+            s.span.shift(bytes, lines);
+            for statement in &mut s.body {
+                shift(statement, bytes, lines);
+            }
+        }
+    }
+}
+
+/// Move the location reported by `error`, raised for a chunk of the source, to its position within the whole source.
+fn shift_error(error: TapError, bytes: usize, lines: usize) -> TapError {
+    match error {
+        TapError::Grammar(mut e) => {
+            e.location = match e.location {
+                InputLocation::Pos(p) => InputLocation::Pos(p + bytes),
+                InputLocation::Span((s, e)) => InputLocation::Span((s + bytes, e + bytes)),
+            };
+            e.line_col = match e.line_col {
+                LineColLocation::Pos((l, c)) => LineColLocation::Pos((l + lines, c)),
+                LineColLocation::Span((l1, c1), (l2, c2)) => {
+                    LineColLocation::Span((l1 + lines, c1), (l2 + lines, c2))
+                }
+            };
+            TapError::Grammar(e)
+        }
+        TapError::MissingPlan(l) => TapError::MissingPlan(l + lines),
+        TapError::MultiplePlans(l) => TapError::MultiplePlans(l + lines),
+        TapError::MisplacedPlan(l) => TapError::MisplacedPlan(l + lines),
+        TapError::MaxDepthExceeded { max_depth, line } => TapError::MaxDepthExceeded {
+            max_depth,
+            line: line + lines,
+        },
+        TapError::TrailingInput { offset, line } => TapError::TrailingInput {
+            offset: offset + bytes,
+            line: line + lines,
+        },
+        e => e,
+    }
+}
+
+pub(crate) fn is_indented(line: &str) -> bool {
+    line.starts_with([' ', '\t'])
+}
+
+fn starts_with_ignore_ascii_case(s: &str, prefix: &str) -> bool {
+    s.get(..prefix.len())
+        .is_some_and(|s| s.eq_ignore_ascii_case(prefix))
+}
+
+/// Splits a document into the chunks of lines forming a top-level construct, and parses each on its own.
+struct Collector<'a> {
+    content: &'a str,
+    /// Byte offset and text, without line terminator, of each line.
+    lines: Vec<(usize, &'a str)>,
+    preamble: Option<Preamble<'a>>,
+    plan: Option<Plan<'a>>,
+    body: Vec<Statement<'a>>,
+    errors: Vec<TapError>,
+    /// Chunks that failed to parse, which have been reported already.
+    recovered: Vec<&'a str>,
+}
+
+impl<'a> Collector<'a> {
+    fn new(content: &'a str) -> Self {
+        let mut offset = 0;
+        let lines = content
+            .split_inclusive('\n')
+            .map(|line| {
+                let start = offset;
+                offset += line.len();
+                (start, line.trim_end_matches(['\r', '\n']))
+            })
+            .collect();
+        Self {
+            content,
+            lines,
+            preamble: None,
+            plan: None,
+            body: Vec::new(),
+            errors: Vec::new(),
+            recovered: Vec::new(),
+        }
+    }
+
+    /// Parse lines `start..end` as a single statement. If that fails, the error is recorded and the lines are kept as
+    /// a [`Statement::Anything`].
+    fn statement(&mut self, start: usize, end: usize) {
+        let (offset, _) = self.lines[start];
+        let (last, text) = self.lines[end - 1];
+        let chunk = &self.content[offset..last + text.len()];
+        match Statement::parse_from_str(chunk) {
+            Ok(mut statement) => {
+                shift(&mut statement, offset, start);
+                self.body.push(statement);
+            }
+            Err(e) => {
+                self.errors.push(shift_error(e, offset, start));
+                self.recovered.push(chunk);
+                self.body.push(Statement::Anything(chunk));
+            }
+        }
+    }
+
+    fn run(mut self) -> (Option<Document<'a>>, Vec<TapError>) {
+        let mut index = 0;
+        while index < self.lines.len() {
+            let (offset, line) = self.lines[index];
+            let trimmed = line.trim_end();
+            let is_subtest_decl = is_subtest_decl(trimmed);
+            let first = self.preamble.is_none()
+                && self.plan.is_none()
+                && self.body.is_empty()
+                && !trimmed.starts_with('#');
+
+            if first && !trimmed.is_empty() {
+                match Preamble::parse_from_str(trimmed) {
+                    Ok(preamble) => {
+                        self.preamble = Some(preamble);
+                        index += 1;
+                        continue;
+                    }
+                    Err(e) => {
+                        self.errors.push(shift_error(e, offset, index));
+                        self.preamble = Some(Preamble { version: "13" });
+                    }
+                }
+            }
+
+            let mut end = index + 1;
+            if trimmed.is_empty() || (trimmed.starts_with('#') && !is_subtest_decl) {
+                /* Comments are dropped, like by `Document::parse_from_str`. */
+            } else if is_subtest_decl || is_indented(line) {
+                while end < self.lines.len()
+                    && (is_indented(self.lines[end].1) || self.lines[end].1.trim().is_empty())
+                {
+                    end += 1;
+                }
+                self.statement(index, end);
+            } else if starts_with_ignore_ascii_case(trimmed, "ok")
+                || starts_with_ignore_ascii_case(trimmed, "not ok")
+            {
+                let yaml = self
+                    .lines
+                    .get(end)
+                    .is_some_and(|(_, l)| is_indented(l) && l.trim() == "---");
+                if yaml {
+                    if let Some(terminator) = self.lines[end..]
+                        .iter()
+                        .position(|(_, l)| l.trim() == "...")
+                    {
+                        end += terminator + 1;
+                    }
+                }
+                self.statement(index, end);
+            } else if trimmed.starts_with(|c: char| c.is_ascii_digit())
+                && Plan::parse_from_str(trimmed).is_ok()
+            {
+                if self.plan.is_some() {
+                    self.errors.push(TapError::MultiplePlans(index + 1));
+                    self.body.push(Statement::Anything(trimmed));
+                } else {
+                    self.plan = Plan::parse_from_str(trimmed).ok();
+                }
+            } else {
+                self.statement(index, end);
+            }
+            index = end;
+        }
+
+        number_tests(&mut self.body);
+        let mut violations = Vec::new();
+        strict_violations(&self.body, &mut violations);
+        let recovered = &self.recovered;
+        violations
+            .retain(|e| !matches!(e, TapError::NonTapLine(l) if recovered.contains(&l.as_str())));
+        self.errors.append(&mut violations);
+        let document = match (self.preamble, self.plan) {
+            (preamble, Some(plan)) => Some(Document {
+                preamble: preamble.unwrap_or(Preamble { version: "13" }),
+                plan,
+                body: self.body,
+                span: Span::from_pest(
+                    pest::Span::new(self.content, 0, self.content.len()).unwrap(),
+                ),
+            }),
+            (_, None) => {
+                self.errors.push(TapError::MissingPlan(1));
+                None
+            }
+        };
+        (document, self.errors)
+    }
+}
+
+impl<'a> Document<'a> {
+    /// Parse [`Document`] from a `&str`, recovering from errors to report every problem rather than just the first.
+    ///
+    /// The `strict` pragma is honored, like by [`Document::parse_from_str_with_pragmas`]. If that succeeds, the
+    /// document is returned without any errors. Otherwise, the content is split into the chunks of lines forming each
+    /// top-level construct, e.g. a test along with its YAML block or a whole subtest, and each chunk is parsed on its
+    /// own. A chunk that fails to parse is kept as a [`Statement::Anything`], and the error is recorded with its
+    /// location adjusted to the whole content.
+    ///
+    /// A missing preamble is reported, but doesn't prevent building the document, defaulting to version `13`. Without
+    /// a plan, no document can be built, and [`TapError::MissingPlan`] is reported for line `1`. The returned errors
+    /// are non-empty whenever the document doesn't parse with [`Document::parse_from_str_with_pragmas`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::{Document, Statement};
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..3\n",
+    ///     "# Subtest: first\n",
+    ///     "    ok 1\n",
+    ///     "ok 1 - first\n",
+    ///     "pragma +strict\n",
+    ///     "garbage\n",
+    ///     "ok 2\n",
+    ///     "# Subtest: second\n",
+    ///     "    ok 1\n",
+    ///     "ok 3 - second\n",
+    /// );
+    /// let (document, errors) = Document::parse_collecting(content);
+    /// let lines: Vec<_> = errors.iter().map(|e| e.to_string().lines().next().unwrap().trim().to_owned()).collect();
+    /// assert_eq!(lines, ["--> 4:9", "--> 10:9", "Line 'garbage' is not valid TAP in strict mode"]);
+    /// let document = document.expect("Missing document");
+    /// assert!(matches!(document.body[0], Statement::Anything("# Subtest: first\n    ok 1")));
+    /// assert_eq!(document.tests().count(), 3);
+    /// assert_eq!(document.tests().nth(1).unwrap().span.start_line, 8);
+    /// ```
+    pub fn parse_collecting(content: &'a str) -> (Option<Self>, Vec<TapError>) {
+        /* The document rule stops at the first statement it can't match, so make sure it covered everything. */
+        match Self::parse_until(content, &ParseOptions::new().pragmas(true)) {
+            Ok((document, end)) if content[end..].trim().is_empty() => (Some(document), Vec::new()),
+            result => {
+                let (document, mut errors) = Collector::new(content).run();
+                if errors.is_empty() {
+                    errors.extend(result.err());
+                }
+                (document, errors)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_collecting_locations() {
+        let content = concat!(
+            "1..2\n",
+            "ok 1\n",
+            "# Subtest\n",
+            "    1..1\n",
+            "    ok 1\n",
+            "    1..1\n",
+            "ok 2\n",
+            "1..2\n",
+        );
+        let (document, errors) = Document::parse_collecting(content);
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(
+            matches!(&errors[0], TapError::Grammar(e) if e.line_col == LineColLocation::Pos((1, 1)))
+        );
+        assert!(matches!(errors[1], TapError::MultiplePlans(6)));
+        assert!(matches!(errors[2], TapError::MultiplePlans(8)));
+
+        let document = document.expect("Missing document");
+        assert_eq!(document.preamble.version, "13");
+        assert_eq!(document.plan.last, 2);
+        let spans: Vec<_> = document.tests().map(|t| t.span.start_line).collect();
+        assert_eq!(spans, [2, 7]);
+
+        let (document, errors) = Document::parse_collecting("TAP version 14\nok 1\n");
+        assert!(document.is_none());
+        assert!(matches!(errors[..], [TapError::MissingPlan(1)]));
+    }
+}
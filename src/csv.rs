@@ -0,0 +1,140 @@
+//! CSV export, e.g. for loading test results into a spreadsheet.
+
+use alloc::{
+    borrow::ToOwned,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{validate::resolve_numbers, Document, Statement};
+
+/// Quote `field` if it contains a comma, a double quote or a line break, doubling any contained double quotes.
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Append a row per test in `statements` to `csv`, recursing into subtests. `path` holds the dotted numbers of the
+/// enclosing subtests.
+fn write_rows(csv: &mut String, statements: &[Statement], path: &str) {
+    let numbers = resolve_numbers(statements);
+    let mut numbers = numbers.iter().peekable();
+    let mut counter = 0;
+    for statement in statements {
+        match statement {
+            Statement::Test(test) => {
+                let (number, _) = numbers.next().unwrap();
+                counter = *number;
+                let directive = test.directive.as_ref();
+                let fields = [
+                    number.to_string(),
+                    if test.result { "ok" } else { "not ok" }.to_owned(),
+                    test.description
+                        .as_deref()
+                        .unwrap_or_default()
+                        .trim()
+                        .to_owned(),
+                    directive.map(|d| d.key.to_string()).unwrap_or_default(),
+                    directive
+                        .and_then(|d| d.reason)
+                        .unwrap_or_default()
+                        .to_owned(),
+                    format!("{}{}", path, number),
+                ];
+                let row: Vec<_> = fields.iter().map(|f| quote(f)).collect();
+                csv.push_str(&row.join(","));
+                csv.push('\n');
+            }
+            Statement::Subtest(subtest) => {
+                /* A subtest is numbered by the test closing it. */
+                let number = numbers
+                    .peek()
+                    .map_or_else(|| counter.saturating_add(1), |(n, _)| *n);
+                write_rows(csv, &subtest.body, &format!("{}{}.", path, number));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Document<'_> {
+    /// Render the document as CSV, with a header row followed by one row per [`crate::Test`].
+    ///
+    /// The columns are `number`, `result` (`ok` or `not ok`), `description`, `directive_key`, `directive_reason` and
+    /// `path`. The path holds the dotted numbers leading to the test, e.g. `1.2` for test `2` inside the
+    /// [`crate::Subtest`] closed by test `1`. Tests nested inside a subtest precede the test closing it.
+    ///
+    /// Only available with the `csv` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..2\n",
+    ///     "# Subtest: sub\n",
+    ///     "    1..1\n",
+    ///     "    ok 1 - inner # SKIP no network, no luck\n",
+    ///     "ok 1 - sub\n",
+    ///     "not ok 2 - outer\n",
+    /// );
+    /// let csv = Document::parse_from_str(content).expect("Parser error").to_csv();
+    /// assert_eq!(
+    ///     csv,
+    ///     concat!(
+    ///         "number,result,description,directive_key,directive_reason,path\n",
+    ///         "1,ok,inner,SKIP,\"no network, no luck\",1.1\n",
+    ///         "1,ok,sub,,,1\n",
+    ///         "2,not ok,outer,,,2\n",
+    ///     )
+    /// );
+    /// ```
+    pub fn to_csv(&self) -> String {
+        let mut csv =
+            String::from("number,result,description,directive_key,directive_reason,path\n");
+        write_rows(&mut csv, &self.body, "");
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_quoting() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..3\n",
+            "ok - say \"hi\"\n",
+            "# Subtest\n",
+            "    1..1\n",
+            "    # Subtest\n",
+            "        1..1\n",
+            "        not ok 1 - deep # TODO a, b\n",
+            "    not ok 1\n",
+            "not ok 2\n",
+            "ok 3\n",
+        );
+        let csv = Document::parse_from_str(content)
+            .expect("Parser error")
+            .to_csv();
+        assert_eq!(
+            csv,
+            concat!(
+                "number,result,description,directive_key,directive_reason,path\n",
+                "1,ok,\"say \"\"hi\"\"\",,,1\n",
+                "1,not ok,deep,TODO,\"a, b\",2.1.1\n",
+                "1,not ok,,,,2.1\n",
+                "2,not ok,,,,2\n",
+                "3,ok,,,,3\n",
+            )
+        );
+    }
+}
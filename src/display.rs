@@ -0,0 +1,551 @@
+//! [`Display`] implementations re-emitting canonical TAP14.
+//!
+//! Nested [`Subtest`]s are indented by two spaces per nesting level, YAML blocks by two spaces relative to their
+//! [`Test`]. [`Document::to_tap_string`] takes [`DisplayOptions`] to deviate from that.
+
+use alloc::{format, string::String};
+use core::fmt::{self, Display, Formatter};
+
+use crate::{
+    trim_trailing, BailOut, Directive, Document, Key, OwnedDocument, Plan, Pragma, Preamble,
+    Statement, Subtest, Test,
+};
+
+/// Options controlling how [`Document::to_tap_string`] renders a [`Document`]. The [`Default`] options match its
+/// [`Display`] implementation.
+///
+/// # Examples
+///
+/// ```
+/// use tapconsooomer::{DisplayOptions, Document};
+///
+/// let content = concat!(
+///     "TAP version 14\n",
+///     "1..2\n",
+///     "# Subtest: foo\n",
+///     "    1..1\n",
+///     "    ok 1 - bar()\n",
+///     "ok 1 - foo\n",
+///     "not ok 2 - baz() # TODO not implemented\n",
+/// );
+/// let doc = Document::parse_from_str(content).expect("Parser error");
+/// assert_eq!(
+///     doc.to_tap_string(&DisplayOptions::new().description_dash(false).indent(4)),
+///     concat!(
+///         "TAP version 14\n",
+///         "1..2\n",
+///         "# Subtest: foo\n",
+///         "    1..1\n",
+///         "    ok 1 bar()\n",
+///         "ok 1 foo\n",
+///         "not ok 2 baz() # TODO not implemented\n",
+///     )
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DisplayOptions {
+    /// Whether to separate the description of a [`Test`] from its number by ` - ` rather than a single space. [`None`]
+    /// keeps the producer's choice recorded in [`Test::dash`]. The dash is kept regardless where omitting it would
+    /// change the meaning of the line, i.e. if the description starts with a `-`, or with a digit while the test has
+    /// no number. Defaults to [`None`].
+    pub description_dash: Option<bool>,
+    /// Number of spaces each [`Subtest`] and YAML block is indented by, relative to its parent. At least one space is
+    /// always used, as indentation is what sets them apart. Defaults to `2`.
+    pub indent: usize,
+}
+
+impl Default for DisplayOptions {
+    fn default() -> Self {
+        Self {
+            description_dash: None,
+            indent: 2,
+        }
+    }
+}
+
+impl DisplayOptions {
+    /// Create the [`Default`] options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set [`DisplayOptions::description_dash`], to always include (`true`) or omit (`false`) the dash.
+    pub fn description_dash(mut self, enabled: bool) -> Self {
+        self.description_dash = Some(enabled);
+        self
+    }
+
+    /// Set [`DisplayOptions::indent`].
+    pub fn indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    fn indentation(&self) -> String {
+        " ".repeat(self.indent.max(1))
+    }
+}
+
+impl Display for Preamble<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "TAP version {}", self.version)
+    }
+}
+
+impl Display for Plan<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.first, self.last)?;
+        if let Some(reason) = self.reason {
+            write!(f, " # {}", reason)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Key<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Key::Skip => "SKIP",
+            Key::Todo => "TODO",
+            Key::Other(key) => key,
+        })
+    }
+}
+
+impl Display for Directive<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "# {}", self.key)?;
+        if let Some(reason) = self.reason {
+            write!(f, " {}", reason)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for BailOut<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("Bail out!")?;
+        if let Some(reason) = self.reason {
+            write!(f, " {}", reason)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Pragma<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("pragma ")?;
+        match self.flag {
+            Some(true) => f.write_str("+")?,
+            Some(false) => f.write_str("-")?,
+            None => {}
+        }
+        f.write_str(self.option)
+    }
+}
+
+impl Test<'_> {
+    fn fmt_indented(
+        &self,
+        f: &mut Formatter<'_>,
+        indent: &str,
+        options: &DisplayOptions,
+    ) -> fmt::Result {
+        f.write_str(indent)?;
+        f.write_str(if self.result { "ok" } else { "not ok" })?;
+        match (self.number, self.raw_number) {
+            /* Keep the original form (e.g. leading zeros) unless the number was changed since parsing. */
+            (Some(number), Some(raw)) if raw.parse() == Ok(number) => write!(f, " {}", raw)?,
+            (Some(number), _) => write!(f, " {}", number)?,
+            (None, _) => {}
+        }
+        if let Some(description) = &self.description {
+            let dash = options.description_dash.unwrap_or(self.dash)
+                || description.starts_with('-')
+                || (self.number.is_none() && description.starts_with(|c: char| c.is_ascii_digit()));
+            f.write_str(if dash { " - " } else { " " })?;
+            f.write_str(&description.replace('#', "\\#"))?;
+        }
+        if let Some(directive) = &self.directive {
+            write!(f, " {}", directive)?;
+        }
+        if !self.yaml.is_empty() {
+            let inner = format!("{}{}", indent, options.indentation());
+            write!(f, "\n{}---", inner)?;
+            for line in &self.yaml {
+                write!(f, "\n{}{}", inner, line)?;
+            }
+            write!(f, "\n{}...", inner)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Test<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_indented(f, "", &DisplayOptions::default())
+    }
+}
+
+/// The name declared by the subtest declaration `raw` as [`Subtest::parse`] reads it, i.e. the text following the
+/// first `:` without surrounding spaces and tabs.
+fn declared_name(raw: &str) -> Option<&str> {
+    raw.split_once(':')
+        .and_then(|(_, name)| trim_trailing(name.trim_start_matches([' ', '\t'])))
+}
+
+impl Subtest<'_> {
+    fn fmt_indented(
+        &self,
+        f: &mut Formatter<'_>,
+        indent: &str,
+        options: &DisplayOptions,
+    ) -> fmt::Result {
+        f.write_str(indent)?;
+        match (self.name, self.raw_declaration) {
+            /* Keep the original form (e.g. `# Subtest:foo`) unless the name was changed since parsing. */
+            (name, Some(raw)) if declared_name(raw) == name => f.write_str(raw)?,
+            (Some(name), _) => write!(f, "# Subtest: {}", name)?,
+            (None, _) => f.write_str("# Subtest")?,
+        }
+        let inner = format!("{}{}", indent, options.indentation());
+        write!(f, "\n{}{}", inner, self.plan)?;
+        for statement in &self.body {
+            f.write_str("\n")?;
+            statement.fmt_indented(f, &inner, options)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Subtest<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_indented(f, "", &DisplayOptions::default())
+    }
+}
+
+impl Statement<'_> {
+    fn fmt_indented(
+        &self,
+        f: &mut Formatter<'_>,
+        indent: &str,
+        options: &DisplayOptions,
+    ) -> fmt::Result {
+        match self {
+            Statement::Anything(a) => write!(f, "{}{}", indent, a),
+            Statement::Comment("") => write!(f, "{}#", indent),
+            Statement::Comment(c) => write!(f, "{}# {}", indent, c),
+            Statement::BailOut(b) => write!(f, "{}{}", indent, b),
+            Statement::Pragma(p) => write!(f, "{}{}", indent, p),
+            Statement::Subtest(s) => s.fmt_indented(f, indent, options),
+            Statement::Test(t) => t.fmt_indented(f, indent, options),
+        }
+    }
+}
+
+impl Display for Statement<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_indented(f, "", &DisplayOptions::default())
+    }
+}
+
+/// Renders the [`Document`] as TAP14, always placing the [`Plan`] ahead of the body.
+///
+/// # Examples
+///
+/// ```
+/// use tapconsooomer::Document;
+///
+/// let content = concat!(
+///     "TAP version 14\n",
+///     "ok 1 - foo()\n",
+///     "not ok 2 - bar() # TODO not implemented\n",
+///     "1..2\n",
+/// );
+/// let doc = Document::parse_from_str(content).expect("Parser error");
+/// assert_eq!(
+///     doc.to_string(),
+///     concat!(
+///         "TAP version 14\n",
+///         "1..2\n",
+///         "ok 1 - foo()\n",
+///         "not ok 2 - bar() # TODO not implemented\n",
+///     )
+/// );
+/// ```
+impl Display for Document<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, &DisplayOptions::default())
+    }
+}
+
+impl Document<'_> {
+    /// Render the [`Document`] as TAP14 like its [`Display`] implementation does, but tuned by `options`, e.g. for
+    /// consumers picky about the separator between test number and description.
+    ///
+    /// See [`DisplayOptions`] for an example.
+    pub fn to_tap_string(&self, options: &DisplayOptions) -> String {
+        struct WithOptions<'d, 'a>(&'d Document<'a>, &'d DisplayOptions);
+
+        impl Display for WithOptions<'_, '_> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                self.0.fmt_with(f, self.1)
+            }
+        }
+
+        format!("{}", WithOptions(self, options))
+    }
+
+    fn fmt_with(&self, f: &mut Formatter<'_>, options: &DisplayOptions) -> fmt::Result {
+        writeln!(f, "{}", self.preamble)?;
+        writeln!(f, "{}", self.plan)?;
+        for statement in &self.body {
+            statement.fmt_indented(f, "", options)?;
+            f.write_str("\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// Renders the [`OwnedDocument`] like its borrowed counterpart, see [`OwnedDocument::as_borrowed`].
+impl Display for OwnedDocument {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.as_borrowed().fmt(f)
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use alloc::{format, string::ToString, vec::Vec};
+    use serde_json::Value;
+    use std::fs;
+
+    use super::DisplayOptions;
+    use crate::Document;
+
+    /// Serialize `doc`, dropping source locations which naturally differ after re-emitting.
+    fn to_value_without_spans(doc: &Document) -> Value {
+        fn strip(value: &mut Value) {
+            match value {
+                Value::Object(map) => {
+                    map.remove("span");
+                    map.values_mut().for_each(strip);
+                }
+                Value::Array(values) => values.iter_mut().for_each(strip),
+                _ => {}
+            }
+        }
+
+        let mut value = serde_json::to_value(doc).unwrap();
+        strip(&mut value);
+        value
+    }
+
+    fn assert_round_trip(content: &str) {
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        let rendered = doc.to_string();
+        let reparsed =
+            Document::parse_from_str(&rendered).expect("Parser error on re-emitted document");
+        assert_eq!(
+            to_value_without_spans(&doc),
+            to_value_without_spans(&reparsed),
+            "re-emitted document:\n{}",
+            rendered
+        );
+    }
+
+    #[test]
+    fn test_round_trip_examples() {
+        for example in [
+            "cascading",
+            "common",
+            "small",
+            "yaml",
+            "move_mount_set_group_test",
+        ] {
+            let contents = fs::read_to_string(format!("examples/{}.tap", example))
+                .expect("Failed to read file");
+            assert_round_trip(&contents);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_nested_subtests() {
+        assert_round_trip(concat!(
+            "TAP version 14\n",
+            "1..2\n",
+            "# Subtest: outer\n",
+            "    # Subtest: inner\n",
+            "        1..1\n",
+            "        not ok 1 - deep # SKIP no reason\n",
+            "          ---\n",
+            "          message: boom\n",
+            "          ...\n",
+            "    1..1\n",
+            "ok 1 - outer\n",
+            "Bail out! done\n",
+        ));
+    }
+
+    #[test]
+    fn test_escaped_hash_round_trip() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..1\n",
+            "ok 1 - compute a \\# b # TODO later\n",
+        );
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        assert_eq!(doc.to_string(), content);
+    }
+
+    #[test]
+    fn test_compact_description_keeps_ambiguous_dashes() {
+        let doc = Document::parse_from_str(concat!(
+            "TAP version 14\n",
+            "1..3\n",
+            "ok 1 - foo\n",
+            "ok - -1 is negative\n",
+            "ok - 3 apples\n",
+            "# Subtest\n",
+            "    1..1\n",
+            "    not ok 1 - bar\n",
+            "      ---\n",
+            "      message: boom\n",
+            "      ...\n",
+            "not ok 2 - baz\n",
+        ))
+        .expect("Parser error");
+        let rendered = doc.to_tap_string(&DisplayOptions::new().description_dash(false).indent(0));
+        assert_eq!(
+            rendered,
+            concat!(
+                "TAP version 14\n",
+                "1..3\n",
+                "ok 1 foo\n",
+                "ok - -1 is negative\n",
+                "ok - 3 apples\n",
+                "# Subtest\n",
+                " 1..1\n",
+                " not ok 1 bar\n",
+                "  ---\n",
+                "  message: boom\n",
+                "  ...\n",
+                "not ok 2 baz\n",
+            )
+        );
+        let descriptions = |doc: &Document| -> Vec<_> {
+            doc.tests()
+                .map(|t| t.description.clone().map(|d| d.into_owned()))
+                .collect()
+        };
+        let reparsed = Document::parse_from_str(&rendered).expect("Parser error");
+        assert_eq!(descriptions(&reparsed), descriptions(&doc));
+    }
+
+    #[test]
+    fn test_description_dash_round_trip() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..4\n",
+            "ok 1 - foo\n",
+            "ok 2 bar\n",
+            "ok 3 - - starts with a dash\n",
+            "ok 4\n",
+        );
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        let dashes: Vec<_> = doc.tests().map(|t| t.dash).collect();
+        assert_eq!(dashes, [true, false, true, false]);
+        assert_eq!(
+            doc.tests().nth(2).unwrap().description.as_deref(),
+            Some("- starts with a dash")
+        );
+        assert_eq!(doc.to_string(), content);
+        assert_eq!(
+            doc.to_tap_string(&DisplayOptions::new().description_dash(true)),
+            content.replace("ok 2 bar", "ok 2 - bar")
+        );
+    }
+
+    #[test]
+    fn test_subtest_declaration_round_trip() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..2\n",
+            "# Subtest:outer\n",
+            "    1..2\n",
+            "    #  SUBTEST:  inner  \n",
+            "        1..1\n",
+            "        ok 1\n",
+            "    # subtest\n",
+            "        1..1\n",
+            "        ok 1\n",
+            "ok 1 - outer\n",
+            "# Subtest: \n",
+            "    1..1\n",
+            "    ok 1\n",
+            "ok 2\n",
+        );
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        assert_eq!(doc.to_tap_string(&DisplayOptions::new().indent(4)), content);
+
+        let mut owned = doc.into_owned();
+        assert!(owned.rename_subtest(&["outer"], "renamed"));
+        assert!(owned.to_string().starts_with(
+            "TAP version 14\n1..2\n# Subtest: renamed\n  1..2\n  #  SUBTEST:  inner  \n"
+        ));
+
+        /* A lone `\r` terminates the declaration as well. */
+        for content in [
+            "TAP version 14\r1..1\r# Subtest\r    1..1\r    ok 1\rok 1 - sub\r",
+            "TAP version 14\r1..1\r# Subtest: sub\r    1..1\r    ok 1\rok 1 - sub\r",
+        ] {
+            let doc = Document::parse_from_str(content).expect("Parser error");
+            assert_eq!(
+                doc.to_tap_string(&DisplayOptions::new().indent(4)),
+                content.replace('\r', "\n")
+            );
+        }
+
+        /* The declared name may hold a colon itself, so renaming it to its own suffix has to be noticed as well. */
+        let content = concat!(
+            "TAP version 14\n",
+            "1..1\n",
+            "# Subtest:a: b\n",
+            "    1..1\n",
+            "    ok 1\n",
+            "ok 1 - a: b\n",
+        );
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        assert_eq!(doc.to_tap_string(&DisplayOptions::new().indent(4)), content);
+        let mut owned = doc.into_owned();
+        assert!(owned.rename_subtest(&["a: b"], "b"));
+        assert!(owned
+            .to_string()
+            .starts_with("TAP version 14\n1..1\n# Subtest: b\n"));
+    }
+
+    #[test]
+    fn test_subtest_indentation() {
+        let doc = Document::parse_from_str(concat!(
+            "TAP version 14\n",
+            "1..1\n",
+            "# Subtest: outer\n",
+            "    1..1\n",
+            "    ok 1 - inner\n",
+            "ok 1 - outer\n",
+        ))
+        .expect("Parser error");
+        assert_eq!(
+            doc.to_string(),
+            concat!(
+                "TAP version 14\n",
+                "1..1\n",
+                "# Subtest: outer\n",
+                "  1..1\n",
+                "  ok 1 - inner\n",
+                "ok 1 - outer\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! Test durations reported by YAML diagnostics.
+
+use core::time::Duration;
+
+use crate::{
+    location::{split, unquote},
+    Statement, Test,
+};
+
+/// Keys conventionally holding a duration, optionally followed by `_` or `-` and the unit of unit-less values.
+const KEYS: [&str; 4] = ["duration", "time", "elapsed", "runtime"];
+
+/// Length of `unit` in seconds, accepting the usual abbreviations case-insensitively.
+fn unit_secs(unit: &str) -> Option<f64> {
+    let is = |units: &[&str]| units.iter().any(|u| u.eq_ignore_ascii_case(unit));
+    if is(&["ns", "nanos", "nanoseconds"]) {
+        Some(1e-9)
+    } else if is(&["us", "µs", "micros", "microseconds"]) {
+        Some(1e-6)
+    } else if is(&["ms", "millis", "milliseconds"]) {
+        Some(1e-3)
+    } else if is(&["s", "sec", "secs", "second", "seconds"]) {
+        Some(1.0)
+    } else if is(&["m", "min", "mins", "minute", "minutes"]) {
+        Some(60.0)
+    } else if is(&["h", "hr", "hrs", "hour", "hours"]) {
+        Some(3600.0)
+    } else {
+        None
+    }
+}
+
+/// Parse `value` held by `key`, e.g. `duration_ms: 12.5` or `time: 1.2s`. Yields [`None`] if `key` doesn't hold a
+/// duration, or `value` isn't one, e.g. a timestamp.
+fn parse_duration(key: &str, value: &str) -> Option<Duration> {
+    let (base, key_unit) = match key.find(['_', '-']) {
+        Some(i) => (&key[..i], Some(&key[i + 1..])),
+        None => (key, None),
+    };
+    if !KEYS.iter().any(|k| k.eq_ignore_ascii_case(base)) {
+        return None;
+    }
+    let default = match key_unit {
+        Some(unit) => unit_secs(unit)?,
+        None => 1.0,
+    };
+    let value = unquote(value);
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let (number, unit) = (value[..split].parse::<f64>().ok()?, value[split..].trim());
+    let factor = if unit.is_empty() {
+        default
+    } else {
+        unit_secs(unit)?
+    };
+    Duration::try_from_secs_f64(number * factor).ok()
+}
+
+impl Test<'_> {
+    /// Look up how long the test took, as reported by its YAML block, e.g. for listing the slowest tests.
+    ///
+    /// The duration is taken from the first top-level key named `duration`, `time`, `elapsed` or `runtime`, matched
+    /// case-insensitively, holding a non-negative number. The unit may follow the number, e.g. `1.5s` or `350 ms`, or
+    /// be appended to the key, e.g. `duration_ms: 350`. Unit-less values of unit-less keys are taken as seconds. Values
+    /// that aren't a duration, like timestamps, are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::time::Duration;
+    /// use tapconsooomer::Test;
+    ///
+    /// let content = "ok 1 - foo()\n  ---\n  duration_ms: 12.5\n  ...\n";
+    /// let test = Test::parse_from_str(content).expect("Parser error");
+    /// assert_eq!(test.duration(), Some(Duration::from_micros(12_500)));
+    ///
+    /// let content = "ok 2 - bar()\n  ---\n  time: '2024-05-01T12:00:00Z'\n  Elapsed: 1.5 s\n  ...\n";
+    /// let test = Test::parse_from_str(content).expect("Parser error");
+    /// assert_eq!(test.duration(), Some(Duration::from_millis(1500)));
+    ///
+    /// assert_eq!(Test::parse_from_str("ok 3").unwrap().duration(), None);
+    /// ```
+    pub fn duration(&self) -> Option<Duration> {
+        let base = self
+            .yaml
+            .iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| split(line).0)
+            .min()?;
+        self.yaml
+            .iter()
+            .map(|line| split(line))
+            .filter(|(indent, _)| *indent == base)
+            .find_map(|(_, pair)| pair.and_then(|(key, value)| parse_duration(key, value)))
+    }
+}
+
+/// Sum up the durations of the tests within `statements`, or [`None`] if none reported any. The closing test of a
+/// subtest reporting a duration covers the tests nested inside, which aren't counted again. The sum saturates at
+/// [`Duration::MAX`].
+pub(crate) fn total_duration(statements: &[Statement]) -> Option<Duration> {
+    let mut total = None;
+    let mut nested = None;
+    for statement in statements {
+        let duration = match statement {
+            Statement::Subtest(subtest) => {
+                nested = total_duration(&subtest.body);
+                continue;
+            }
+            Statement::Test(test) => test.duration().or_else(|| nested.take()),
+            _ => continue,
+        };
+        nested = None;
+        total = add(total, duration);
+    }
+    add(total, nested)
+}
+
+/// Add up two optional durations, saturating at [`Duration::MAX`].
+fn add(a: Option<Duration>, b: Option<Duration>) -> Option<Duration> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.saturating_add(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration_units() {
+        let cases = [
+            ("duration_ms", "250", Some(Duration::from_millis(250))),
+            ("DURATION-MS", "\"0.5\"", Some(Duration::from_micros(500))),
+            ("duration", "2", Some(Duration::from_secs(2))),
+            ("time", "3min", Some(Duration::from_secs(180))),
+            ("time_s", "40 ms", Some(Duration::from_millis(40))),
+            ("runtime", "1h", Some(Duration::from_secs(3600))),
+            ("elapsed", "750us", Some(Duration::from_micros(750))),
+            ("duration", "-1", None),
+            ("duration", "12:30:01", None),
+            ("duration_weeks", "1", None),
+            ("timeout", "5", None),
+            ("message", "5", None),
+        ];
+        for (key, value, expected) in cases {
+            assert_eq!(parse_duration(key, value), expected, "{}: {}", key, value);
+        }
+    }
+
+    #[test]
+    fn test_total_duration_saturates() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..2\n",
+            "ok 1\n",
+            "  ---\n",
+            "  duration: 18000000000000000000\n",
+            "  ...\n",
+            "ok 2\n",
+            "  ---\n",
+            "  duration: 18000000000000000000\n",
+            "  ...\n",
+        );
+        let doc = crate::Document::parse_from_str(content).expect("Parser error");
+        assert_eq!(doc.summary().total_duration, Some(Duration::MAX));
+    }
+}
@@ -0,0 +1,324 @@
+use alloc::{boxed::Box, string::String};
+use core::{
+    fmt::{self, Display, Formatter},
+    num::ParseIntError,
+};
+#[cfg(feature = "std")]
+use std::{error::Error, io};
+
+use crate::Rule;
+
+/// A limit imposed by [`crate::ParseOptions`], see [`TapError::LimitExceeded`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Limit {
+    /// [`crate::ParseOptions::max_bytes`].
+    Bytes,
+    /// [`crate::ParseOptions::max_statements`].
+    Statements,
+}
+
+/// Renders the unit of the limit, e.g. `bytes`.
+impl Display for Limit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Bytes => "bytes",
+            Self::Statements => "statements",
+        })
+    }
+}
+
+/// Errors returned when parsing TAP content fails.
+///
+/// # Examples
+///
+/// Matching on the failure mode:
+///
+/// ```
+/// use tapconsooomer::{Plan, TapError};
+///
+/// let err = Plan::parse_from_str("1..99999999999").unwrap_err();
+/// assert!(matches!(err, TapError::PlanParse(_)));
+///
+/// let err = Plan::parse_from_str("one..two").unwrap_err();
+/// assert!(matches!(err, TapError::Grammar(_)));
+/// ```
+#[derive(Debug)]
+pub enum TapError {
+    /// The content does not match the TAP grammar.
+    Grammar(Box<pest::error::Error<Rule>>),
+    /// A test result other than `ok` or `not ok` was encountered.
+    InvalidResult(String),
+    /// A directive key other than `skip` or `todo` was encountered while `pragma +strict` was active.
+    InvalidDirectiveKey(String),
+    /// A number in a [`crate::Plan`] could not be parsed.
+    PlanParse(ParseIntError),
+    /// A test number could not be parsed, see [`crate::parse_number`].
+    NumberParse(ParseIntError),
+    /// A YAML block could not be parsed into a structured value.
+    #[cfg(feature = "serde")]
+    Yaml(serde_yaml::Error),
+    /// A parsed document could not be serialized to JSON, see [`crate::parse_to_json`].
+    #[cfg(feature = "serde")]
+    Json(serde_json::Error),
+    /// A [`crate::Subtest`] without a plan was encountered. Holds the 1-based line the subtest starts at. Also reported
+    /// for a whole document by [`crate::Document::parse_collecting`].
+    MissingPlan(usize),
+    /// A [`crate::Subtest`] declaring more than one plan was encountered. Holds the 1-based line of the surplus plan. Also
+    /// reported for a whole document by [`crate::Document::parse_collecting`].
+    MultiplePlans(usize),
+    /// A plan in the middle of a body, i.e. followed by further tests, was encountered while
+    /// [`crate::ParseOptions::strict_plan_position`] was active. Holds the 1-based line of the plan.
+    MisplacedPlan(usize),
+    /// A test without an explicit number was found during strict validation. Holds the test's 1-based position in
+    /// document order.
+    MissingTestNumber(usize),
+    /// A line that is not valid TAP was encountered while `pragma +strict` was active.
+    NonTapLine(String),
+    /// The content is nested deeper than [`crate::ParseOptions::max_depth`] allows.
+    MaxDepthExceeded {
+        /// The exceeded limit.
+        max_depth: usize,
+        /// The 1-based line at which the limit was exceeded.
+        line: usize,
+    },
+    /// Non-whitespace content follows the end of the document, see [`crate::Document::parse_exact`].
+    TrailingInput {
+        /// The byte offset of the first unconsumed non-whitespace character.
+        offset: usize,
+        /// The 1-based line of the first unconsumed non-whitespace character.
+        line: usize,
+    },
+    /// The content exceeds a limit imposed by [`crate::ParseOptions`].
+    LimitExceeded {
+        /// The exceeded limit.
+        limit: Limit,
+        /// The maximum allowed by the limit.
+        max: usize,
+    },
+    /// A `not ok` test with a SKIP directive was encountered under [`crate::SkipFailPolicy::Error`]. Holds the 1-based
+    /// line of the test.
+    SkippedFailure(usize),
+    /// The content is empty or consists of blank lines only, or the parser produced no output for it.
+    Empty,
+    /// Reading the content failed.
+    #[cfg(feature = "std")]
+    Io(io::Error),
+}
+
+impl Display for TapError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Grammar(e) => write!(f, "{}", e),
+            Self::InvalidResult(r) => write!(f, "Result '{}' must be 'ok' or 'not ok'", r),
+            Self::InvalidDirectiveKey(k) => {
+                write!(f, "Directive key '{}' must be 'skip' or 'todo'", k)
+            }
+            Self::PlanParse(e) => write!(f, "Invalid plan number: {}", e),
+            Self::NumberParse(e) => write!(f, "Invalid test number: {}", e),
+            #[cfg(feature = "serde")]
+            Self::Yaml(e) => write!(f, "Invalid YAML block: {}", e),
+            #[cfg(feature = "serde")]
+            Self::Json(e) => write!(f, "Failed to serialize to JSON: {}", e),
+            Self::MissingPlan(l) => write!(f, "Subtest at line {} has no plan", l),
+            Self::MultiplePlans(l) => {
+                write!(f, "Plan at line {} is not the first of its subtest", l)
+            }
+            Self::MisplacedPlan(l) => write!(
+                f,
+                "Plan at line {} is neither the first nor the last line of its body",
+                l
+            ),
+            Self::MissingTestNumber(p) => write!(f, "Test #{} has no explicit number", p),
+            Self::NonTapLine(l) => write!(f, "Line '{}' is not valid TAP in strict mode", l),
+            Self::MaxDepthExceeded { max_depth, line } => write!(
+                f,
+                "Line {} is nested deeper than the maximum depth of {}",
+                line, max_depth
+            ),
+            Self::TrailingInput { offset, line } => write!(
+                f,
+                "Unexpected input after the end of the document at line {} (byte offset {})",
+                line, offset
+            ),
+            Self::LimitExceeded { limit, max } => {
+                write!(f, "Content exceeds the limit of {} {}", max, limit)
+            }
+            Self::SkippedFailure(l) => write!(f, "Test at line {} is both failed and skipped", l),
+            Self::Empty => f.write_str("No TAP content to parse"),
+            #[cfg(feature = "std")]
+            Self::Io(e) => write!(f, "Failed to read TAP content: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for TapError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Grammar(e) => Some(e.as_ref()),
+            Self::PlanParse(e) | Self::NumberParse(e) => Some(e),
+            #[cfg(feature = "serde")]
+            Self::Yaml(e) => Some(e),
+            #[cfg(feature = "serde")]
+            Self::Json(e) => Some(e),
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<pest::error::Error<Rule>> for TapError {
+    fn from(e: pest::error::Error<Rule>) -> Self {
+        Self::Grammar(Box::new(e))
+    }
+}
+
+impl From<ParseIntError> for TapError {
+    fn from(e: ParseIntError) -> Self {
+        Self::PlanParse(e)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for TapError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_yaml::Error> for TapError {
+    fn from(e: serde_yaml::Error) -> Self {
+        Self::Yaml(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for TapError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// With the `miette` feature, [`TapError::Grammar`] serves as its own [`miette::SourceCode`], being the offending line
+/// of input.
+#[cfg(feature = "miette")]
+impl miette::SourceCode for TapError {
+    fn read_span<'a>(
+        &'a self,
+        span: &miette::SourceSpan,
+        context_lines_before: usize,
+        context_lines_after: usize,
+    ) -> core::result::Result<Box<dyn miette::SpanContents<'a> + 'a>, miette::MietteError> {
+        match self {
+            Self::Grammar(e) => e
+                .line()
+                .read_span(span, context_lines_before, context_lines_after),
+            _ => Err(miette::MietteError::OutOfBounds),
+        }
+    }
+}
+
+/// With the `miette` feature, a [`TapError::Grammar`] labels the offending part of the line it occurred on.
+///
+/// # Examples
+///
+/// ```
+/// use miette::Diagnostic;
+/// use tapconsooomer::Plan;
+///
+/// let err = Plan::parse_from_str("1..x").unwrap_err();
+/// let label = err.labels().unwrap().next().unwrap();
+/// assert_eq!((label.offset(), label.len()), (0, 4));
+/// ```
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for TapError {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        let code = match self {
+            Self::Grammar(_) => "tap::grammar",
+            Self::InvalidResult(_) => "tap::invalid_result",
+            Self::InvalidDirectiveKey(_) => "tap::invalid_directive_key",
+            Self::PlanParse(_) => "tap::plan_parse",
+            Self::NumberParse(_) => "tap::number_parse",
+            #[cfg(feature = "serde")]
+            Self::Yaml(_) => "tap::yaml",
+            #[cfg(feature = "serde")]
+            Self::Json(_) => "tap::json",
+            Self::MissingPlan(_) => "tap::missing_plan",
+            Self::MultiplePlans(_) => "tap::multiple_plans",
+            Self::MisplacedPlan(_) => "tap::misplaced_plan",
+            Self::MissingTestNumber(_) => "tap::missing_test_number",
+            Self::NonTapLine(_) => "tap::non_tap_line",
+            Self::MaxDepthExceeded { .. } => "tap::max_depth_exceeded",
+            Self::TrailingInput { .. } => "tap::trailing_input",
+            Self::LimitExceeded { .. } => "tap::limit_exceeded",
+            Self::SkippedFailure(_) => "tap::skipped_failure",
+            Self::Empty => "tap::empty",
+            #[cfg(feature = "std")]
+            Self::Io(_) => "tap::io",
+        };
+        Some(Box::new(code))
+    }
+
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        match self {
+            Self::Grammar(_) => Some(self),
+            _ => None,
+        }
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        use pest::error::LineColLocation;
+
+        let Self::Grammar(e) = self else {
+            return None;
+        };
+        let line = e.line().trim_end();
+        /* Columns count characters, whereas miette expects byte offsets. */
+        let offset = |col: usize| {
+            line.char_indices()
+                .nth(col.saturating_sub(1))
+                .map_or(line.len(), |(i, _)| i)
+        };
+        let (start, end) = match e.line_col {
+            /* A position only tells where parsing stopped, so the whole line is highlighted. */
+            LineColLocation::Pos(_) => (0, line.len()),
+            LineColLocation::Span((start_line, start_col), (end_line, end_col)) => (
+                offset(start_col),
+                if end_line == start_line {
+                    offset(end_col)
+                } else {
+                    line.len()
+                },
+            ),
+        };
+        let label = miette::LabeledSpan::new_primary_with_span(
+            Some(e.variant.message().into_owned()),
+            (start, end.max(start) - start),
+        );
+        Some(Box::new(core::iter::once(label)))
+    }
+}
+
+/// Shorthand for a [`core::result::Result`] failing with a [`TapError`].
+pub type Result<T> = core::result::Result<T, TapError>;
+
+#[cfg(all(test, feature = "miette"))]
+mod tests {
+    use alloc::string::ToString;
+    use miette::Diagnostic;
+
+    use crate::Document;
+
+    #[test]
+    fn test_diagnostic_document() {
+        let err = Document::parse_from_str("TAP versión 14\n1..0\n").unwrap_err();
+        assert_eq!(err.code().unwrap().to_string(), "tap::grammar");
+
+        let label = err.labels().unwrap().next().unwrap();
+        assert_eq!(label.label(), Some("expected preamble"));
+        let source = err.source_code().unwrap();
+        let contents = source.read_span(label.inner(), 0, 0).unwrap();
+        assert_eq!(contents.data(), "TAP versión 14".as_bytes());
+    }
+}
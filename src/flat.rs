@@ -0,0 +1,244 @@
+//! TOML export, flattening the nested statements into plain lists of tables.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use serde::Serialize;
+
+use crate::{Document, Plan, Statement};
+
+#[derive(Serialize)]
+struct FlatDocument<'a> {
+    version: &'a str,
+    bailed_out: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bail_out_reason: Option<&'a str>,
+    plan: FlatPlan<'a>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    test: Vec<FlatTest<'a>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    subtest: Vec<FlatSubtest<'a>>,
+}
+
+#[derive(Serialize)]
+struct FlatPlan<'a> {
+    first: i32,
+    last: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<&'a str>,
+}
+
+impl<'a> From<&Plan<'a>> for FlatPlan<'a> {
+    fn from(plan: &Plan<'a>) -> Self {
+        Self {
+            first: plan.first,
+            last: plan.last,
+            reason: plan.reason,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct FlatTest<'a> {
+    path: String,
+    depth: usize,
+    number: i32,
+    result: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    directive: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<&'a str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    yaml: Vec<&'a str>,
+}
+
+#[derive(Serialize)]
+struct FlatSubtest<'a> {
+    path: String,
+    depth: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    plan: FlatPlan<'a>,
+}
+
+/// Append the tests and subtests of `statements` to `document`, recursing into subtests. `path` holds the dotted
+/// numbers of the enclosing subtests.
+fn flatten<'a>(
+    document: &mut FlatDocument<'a>,
+    statements: &'a [Statement<'a>],
+    path: &str,
+    depth: usize,
+) {
+    let mut number = 0;
+    for (index, statement) in statements.iter().enumerate() {
+        match statement {
+            Statement::Test(test) => {
+                number = test.effective_number;
+                let directive = test.directive.as_ref();
+                document.test.push(FlatTest {
+                    path: format!("{}{}", path, number),
+                    depth,
+                    number,
+                    result: test.result,
+                    description: test.description.as_deref().map(str::trim),
+                    directive: directive.map(|d| d.key.to_string()),
+                    reason: directive.and_then(|d| d.reason),
+                    yaml: test.yaml.clone(),
+                });
+            }
+            Statement::Subtest(subtest) => {
+                /* A subtest is numbered by the test closing it. */
+                let closing = statements[index + 1..].iter().find_map(|s| match s {
+                    Statement::Test(test) => Some(test.effective_number),
+                    _ => None,
+                });
+                let path = format!(
+                    "{}{}",
+                    path,
+                    closing.unwrap_or_else(|| number.saturating_add(1))
+                );
+                flatten(document, &subtest.body, &format!("{}.", path), depth + 1);
+                document.subtest.push(FlatSubtest {
+                    path,
+                    depth,
+                    name: subtest.name,
+                    plan: FlatPlan::from(&subtest.plan),
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Document<'_> {
+    /// Render the document as TOML, flattened into a `[[test]]` list of every [`crate::Test`] and a `[[subtest]]`
+    /// list of every [`crate::Subtest`].
+    ///
+    /// Unlike serializing the document through serde, which mirrors the nesting of the statements, this
+    /// representation only nests tables one level deep and has no `null` values, which TOML can't express. Each
+    /// entry has a `path` holding the dotted numbers leading to it, e.g. `1.2` for test `2` inside the subtest closed
+    /// by test `1`, and its `depth`, `0` being the top-level. A test's `number` is its [`crate::Test::effective_number`].
+    /// Tests nested inside a subtest precede the test closing it. Missing values are omitted, while pragmas, comments
+    /// and non-TAP lines are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..2\n",
+    ///     "# Subtest: sub\n",
+    ///     "    1..1\n",
+    ///     "    ok - inner # SKIP no network\n",
+    ///     "ok 1 - sub\n",
+    ///     "not ok 2 - outer\n",
+    /// );
+    /// let toml = Document::parse_from_str(content).expect("Parser error").to_toml();
+    /// assert_eq!(
+    ///     toml,
+    ///     concat!(
+    ///         "version = \"14\"\n",
+    ///         "bailed_out = false\n",
+    ///         "\n",
+    ///         "[plan]\n",
+    ///         "first = 1\n",
+    ///         "last = 2\n",
+    ///         "\n",
+    ///         "[[test]]\n",
+    ///         "path = \"1.1\"\n",
+    ///         "depth = 1\n",
+    ///         "number = 1\n",
+    ///         "result = true\n",
+    ///         "description = \"inner\"\n",
+    ///         "directive = \"SKIP\"\n",
+    ///         "reason = \"no network\"\n",
+    ///         "\n",
+    ///         "[[test]]\n",
+    ///         "path = \"1\"\n",
+    ///         "depth = 0\n",
+    ///         "number = 1\n",
+    ///         "result = true\n",
+    ///         "description = \"sub\"\n",
+    ///         "\n",
+    ///         "[[test]]\n",
+    ///         "path = \"2\"\n",
+    ///         "depth = 0\n",
+    ///         "number = 2\n",
+    ///         "result = false\n",
+    ///         "description = \"outer\"\n",
+    ///         "\n",
+    ///         "[[subtest]]\n",
+    ///         "path = \"1\"\n",
+    ///         "depth = 0\n",
+    ///         "name = \"sub\"\n",
+    ///         "\n",
+    ///         "[subtest.plan]\n",
+    ///         "first = 1\n",
+    ///         "last = 1\n",
+    ///     )
+    /// );
+    /// ```
+    pub fn to_toml(&self) -> String {
+        let mut document = FlatDocument {
+            version: self.preamble.version,
+            bailed_out: false,
+            bail_out_reason: None,
+            plan: FlatPlan::from(&self.plan),
+            test: Vec::new(),
+            subtest: Vec::new(),
+        };
+        if let Some(bail_out) = self.bail_out_nested() {
+            document.bailed_out = true;
+            document.bail_out_reason = bail_out.reason;
+        }
+        flatten(&mut document, &self.body, "", 0);
+        toml::to_string_pretty(&document).expect("Flat document is valid TOML")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_to_toml_roundtrip() {
+        for example in ["cascading", "common", "yaml", "move_mount_set_group_test"] {
+            let contents = fs::read_to_string(format!("examples/{}.tap", example))
+                .expect("Failed to read file");
+            let doc = Document::parse_from_str(&contents).expect("Parser error");
+            let value: toml::Table = doc.to_toml().parse().expect("TOML error");
+            let tests = value.get("test").and_then(|t| t.as_array());
+            assert_eq!(
+                tests.map_or(0, Vec::len),
+                doc.tests().count(),
+                "{}",
+                example
+            );
+        }
+    }
+
+    #[test]
+    fn test_to_toml_nested_bail_out() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..1\n",
+            "# Subtest: inner\n",
+            "    1..2\n",
+            "    ok 1\n",
+            "    Bail out! disk full\n",
+        );
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        let value: toml::Table = doc.to_toml().parse().expect("TOML error");
+        assert_eq!(value["bailed_out"].as_bool(), Some(true));
+        assert_eq!(value["bail_out_reason"].as_str(), Some("disk full"));
+    }
+}
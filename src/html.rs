@@ -0,0 +1,254 @@
+//! HTML export, rendering a self-contained page for viewing test results in a browser.
+
+use alloc::string::{String, ToString};
+use core::fmt::Write;
+
+use crate::{Document, Key, Statement, Test};
+
+/// Inline style sheet, so the page doesn't depend on any external resources.
+const STYLE: &str = "\
+body { font-family: system-ui, sans-serif; margin: 2em; color: #222; }
+.banner { padding: 1em; border-radius: 4px; font-weight: bold; color: #fff; }
+.banner.passed { background: #2e7d32; }
+.banner.failed { background: #c62828; }
+ul { list-style: none; padding-left: 1.5em; }
+li { margin: 0.25em 0; }
+.pass::before { content: '\\2714  '; color: #2e7d32; }
+.fail::before { content: '\\2718  '; color: #c62828; }
+.skip::before { content: '\\21b7  '; color: #757575; }
+.todo::before { content: '\\25cb  '; color: #ef6c00; }
+.directive { color: #757575; font-style: italic; }
+pre { background: #f5f5f5; padding: 0.5em; overflow-x: auto; }
+summary { cursor: pointer; }
+";
+
+/// Escape `s` for use in HTML text and attribute values.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Whether `test` fails the run, i.e. is `not ok` without a [`Key::Todo`] directive. Like [`Document::summary`], a
+/// `not ok` test with a [`Key::Skip`] directive counts as skipped, see [`Test::is_skipped_failure`].
+fn is_failure(test: &Test) -> bool {
+    !test.result
+        && !test.is_skipped_failure()
+        && !matches!(&test.directive, Some(d) if d.key == Key::Todo)
+}
+
+/// Whether any test in `statements`, including nested ones, fails the run.
+fn has_failure(statements: &[Statement]) -> bool {
+    statements.iter().any(|statement| match statement {
+        Statement::Test(test) => is_failure(test),
+        Statement::Subtest(subtest) => has_failure(&subtest.body),
+        _ => false,
+    })
+}
+
+/// Append a `<li>` for `test` to `html`.
+fn write_test(html: &mut String, test: &Test) {
+    let class = match test.directive.as_ref().map(|d| d.key) {
+        Some(Key::Skip) => "skip",
+        Some(Key::Todo) => "todo",
+        _ if test.result => "pass",
+        _ => "fail",
+    };
+    let _ = write!(
+        html,
+        "<li class=\"{}\">{}",
+        class,
+        if test.result { "ok" } else { "not ok" }
+    );
+    if let Some(number) = test.number {
+        let _ = write!(html, " {}", number);
+    }
+    if let Some(description) = test.description.as_deref().map(str::trim) {
+        if !description.is_empty() {
+            let _ = write!(html, " - {}", escape(description));
+        }
+    }
+    if let Some(directive) = &test.directive {
+        let _ = write!(html, " <span class=\"directive\">{}", directive.key);
+        if let Some(reason) = directive.reason {
+            let _ = write!(html, " {}", escape(reason));
+        }
+        html.push_str("</span>");
+    }
+    if !test.yaml.is_empty() {
+        html.push_str("\n<details><summary>Diagnostics</summary><pre>");
+        for line in &test.yaml {
+            let _ = writeln!(html, "{}", escape(line));
+        }
+        html.push_str("</pre></details>");
+    }
+    html.push_str("</li>\n");
+}
+
+/// Append a `<ul>` of the tests and subtests in `statements` to `html`. Subtests are collapsed, unless they contain a
+/// failure.
+fn write_list(html: &mut String, statements: &[Statement]) {
+    html.push_str("<ul>\n");
+    for statement in statements {
+        match statement {
+            Statement::Test(test) => write_test(html, test),
+            Statement::Subtest(subtest) => {
+                let _ = write!(
+                    html,
+                    "<li><details{}><summary>Subtest",
+                    if has_failure(&subtest.body) {
+                        " open"
+                    } else {
+                        ""
+                    }
+                );
+                if let Some(name) = subtest.name {
+                    let _ = write!(html, ": {}", escape(name.trim()));
+                }
+                let _ = writeln!(
+                    html,
+                    " ({}..{})</summary>",
+                    subtest.plan.first, subtest.plan.last
+                );
+                write_list(html, &subtest.body);
+                html.push_str("</details></li>\n");
+            }
+            Statement::BailOut(bail_out) => {
+                html.push_str("<li class=\"fail\">Bail out!");
+                if let Some(reason) = bail_out.reason {
+                    let _ = write!(html, " {}", escape(reason));
+                }
+                html.push_str("</li>\n");
+            }
+            _ => {}
+        }
+    }
+    html.push_str("</ul>\n");
+}
+
+impl Document<'_> {
+    /// Render the document as a standalone HTML page.
+    ///
+    /// The page opens with a banner holding the [`crate::Summary`], colored red if any test failed, not counting TODO
+    /// failures, or the producer bailed out, and green otherwise. It is followed by a list of the tests, each marked by
+    /// its outcome: passed, failed, skipped or TODO. Each [`crate::Subtest`] and YAML block is a collapsible
+    /// `<details>` element, with subtests containing failures expanded. All styling is inlined, so the page has no
+    /// external dependencies and doesn't run any scripts.
+    ///
+    /// Only available with the `html` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..2\n",
+    ///     "ok 1 - foo()\n",
+    ///     "not ok 2 - <bar>\n",
+    ///     "  ---\n",
+    ///     "  message: boom\n",
+    ///     "  ...\n",
+    /// );
+    /// let html = Document::parse_from_str(content).expect("Parser error").to_html();
+    /// assert!(html.starts_with("<!DOCTYPE html>\n"));
+    /// assert!(html.contains("<div class=\"banner failed\">2 tests, 1 passed, 1 failed, 0 skipped, 0 todo</div>"));
+    /// assert!(html.contains("<li class=\"pass\">ok 1 - foo()</li>"));
+    /// assert!(html.contains("<li class=\"fail\">not ok 2 - &lt;bar&gt;\n<details>"));
+    /// assert!(html.contains("<pre>message: boom\n</pre>"));
+    /// ```
+    pub fn to_html(&self) -> String {
+        let failed = has_failure(&self.body) || self.bail_out_nested().is_some();
+        let mut html = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+        html.push_str("<meta charset=\"utf-8\">\n<title>TAP results</title>\n");
+        let _ = writeln!(html, "<style>\n{}</style>\n</head>\n<body>", STYLE);
+        let _ = writeln!(
+            html,
+            "<div class=\"banner {}\">{}</div>",
+            if failed { "failed" } else { "passed" },
+            escape(&self.summary().to_string())
+        );
+        write_list(&mut html, &self.body);
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_subtests() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..3\n",
+            "# Subtest: passing\n",
+            "    1..1\n",
+            "    ok 1 - inner # SKIP not today\n",
+            "ok 1 - passing\n",
+            "# Subtest: failing\n",
+            "    1..1\n",
+            "    not ok 1 - inner\n",
+            "not ok 2 - failing # TODO later\n",
+            "Bail out! boom & bust\n",
+        );
+        let html = Document::parse_from_str(content)
+            .expect("Parser error")
+            .to_html();
+        let start = html.find("<ul>").unwrap();
+        assert_eq!(
+            &html[start..],
+            concat!(
+                "<ul>\n",
+                "<li><details><summary>Subtest: passing (1..1)</summary>\n",
+                "<ul>\n",
+                "<li class=\"skip\">ok 1 - inner <span class=\"directive\">SKIP not today</span></li>\n",
+                "</ul>\n",
+                "</details></li>\n",
+                "<li class=\"pass\">ok 1 - passing</li>\n",
+                "<li><details open><summary>Subtest: failing (1..1)</summary>\n",
+                "<ul>\n",
+                "<li class=\"fail\">not ok 1 - inner</li>\n",
+                "</ul>\n",
+                "</details></li>\n",
+                "<li class=\"todo\">not ok 2 - failing <span class=\"directive\">TODO later</span></li>\n",
+                "<li class=\"fail\">Bail out! boom &amp; bust</li>\n",
+                "</ul>\n",
+                "</body>\n",
+                "</html>\n",
+            )
+        );
+        assert!(html.contains("<div class=\"banner failed\">"));
+    }
+
+    #[test]
+    fn test_html_skipped_failure() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..2\n",
+            "# Subtest: skipped\n",
+            "    1..1\n",
+            "    not ok 1 - inner # SKIP no network\n",
+            "ok 1 - skipped\n",
+            "not ok 2 - outer # SKIP no network\n",
+        );
+        let html = Document::parse_from_str(content)
+            .expect("Parser error")
+            .to_html();
+        assert!(html.contains(
+            "<div class=\"banner passed\">3 tests, 1 passed, 0 failed, 2 skipped, 0 todo</div>"
+        ));
+        assert!(html.contains("<li><details><summary>Subtest: skipped (1..1)</summary>\n"));
+    }
+}
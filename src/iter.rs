@@ -0,0 +1,210 @@
+//! Traversal of [`Test`]s and [`BailOut`]s across nested [`crate::Subtest`]s.
+
+use alloc::{vec, vec::Vec};
+use core::slice;
+
+use crate::{BailOut, Document, Statement, Test};
+
+/// Depth-first iterator over the [`Test`]s of some statements, yielding each along with its nesting depth.
+struct Tests<'d, 'a> {
+    stack: Vec<slice::Iter<'d, Statement<'a>>>,
+}
+
+impl<'d, 'a> Iterator for Tests<'d, 'a> {
+    type Item = (usize, &'d Test<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.last_mut()?.next() {
+                Some(Statement::Test(test)) => return Some((self.stack.len() - 1, test)),
+                Some(Statement::Subtest(subtest)) => self.stack.push(subtest.body.iter()),
+                Some(_) => {}
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+/// Find the first [`BailOut`] in `statements`, descending into subtests.
+fn find_bail_out<'d, 'a>(statements: &'d [Statement<'a>]) -> Option<&'d BailOut<'a>> {
+    statements.iter().find_map(|statement| match statement {
+        Statement::BailOut(bail_out) => Some(bail_out),
+        Statement::Subtest(subtest) => find_bail_out(&subtest.body),
+        _ => None,
+    })
+}
+
+impl<'a> Document<'a> {
+    /// Iterate over all [`Test`]s in document order, descending into [`crate::Subtest`]s depth-first.
+    ///
+    /// The tests of a subtest are yielded before the test line closing the subtest.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..2\n",
+    ///     "# Subtest: sub\n",
+    ///     "    1..1\n",
+    ///     "    ok 1 - inner\n",
+    ///     "ok 1 - sub\n",
+    ///     "not ok 2 - outer\n",
+    /// );
+    /// let doc = Document::parse_from_str(content).expect("Parser error");
+    /// let passed = doc.tests().filter(|t| t.result).count();
+    /// assert_eq!(passed, 2);
+    /// assert_eq!(doc.tests().count(), 3);
+    /// ```
+    pub fn tests(&self) -> impl Iterator<Item = &Test<'a>> + '_ {
+        self.tests_with_depth().map(|(_, test)| test)
+    }
+
+    /// Like [`Document::tests`], but also yield the nesting depth of each [`Test`], `0` being the top-level.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..1\n",
+    ///     "# Subtest: sub\n",
+    ///     "    1..1\n",
+    ///     "    ok 1 - inner\n",
+    ///     "ok 1 - sub\n",
+    /// );
+    /// let doc = Document::parse_from_str(content).expect("Parser error");
+    /// let depths: Vec<_> = doc
+    ///     .tests_with_depth()
+    ///     .map(|(depth, t)| (depth, t.description.as_deref()))
+    ///     .collect();
+    /// assert_eq!(depths, [(1, Some("inner")), (0, Some("sub"))]);
+    /// ```
+    pub fn tests_with_depth(&self) -> impl Iterator<Item = (usize, &Test<'a>)> + '_ {
+        Tests {
+            stack: vec![self.body.iter()],
+        }
+    }
+
+    /// The first top-level [`BailOut`], if the producer bailed out of the whole run.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!("TAP version 14\n", "1..2\n", "ok 1\n", "Bail out! no database\n");
+    /// let doc = Document::parse_from_str(content).expect("Parser error");
+    /// assert_eq!(doc.bail_out().and_then(|b| b.reason), Some("no database"));
+    /// ```
+    pub fn bail_out(&self) -> Option<&BailOut<'a>> {
+        self.body.iter().find_map(|statement| match statement {
+            Statement::BailOut(bail_out) => Some(bail_out),
+            _ => None,
+        })
+    }
+
+    /// Like [`Document::bail_out`], but also search [`crate::Subtest`]s, in document order. Returns [`Some`] exactly
+    /// if [`crate::Summary::bailed_out`] is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..1\n",
+    ///     "# Subtest: sub\n",
+    ///     "    1..2\n",
+    ///     "    ok 1\n",
+    ///     "    Bail out! disk full\n",
+    /// );
+    /// let doc = Document::parse_from_str(content).expect("Parser error");
+    /// assert!(doc.bail_out().is_none());
+    /// assert_eq!(doc.bail_out_nested().and_then(|b| b.reason), Some("disk full"));
+    /// assert!(doc.summary().bailed_out);
+    /// ```
+    pub fn bail_out_nested(&self) -> Option<&BailOut<'a>> {
+        find_bail_out(&self.body)
+    }
+}
+
+/// Iterate over the top-level [`Statement`]s of a [`Document`], i.e. its `body`. Use [`Document::tests`] to also
+/// descend into [`crate::Subtest`]s.
+///
+/// # Examples
+///
+/// ```
+/// use tapconsooomer::{Document, Statement};
+///
+/// let content = concat!(
+///     "TAP version 14\n",
+///     "1..2\n",
+///     "# Subtest: sub\n",
+///     "    1..1\n",
+///     "    ok 1 - inner\n",
+///     "ok 1 - sub\n",
+///     "ok 2 - outer\n",
+/// );
+/// let doc = Document::parse_from_str(content).expect("Parser error");
+/// let mut subtests = 0;
+/// for statement in &doc {
+///     if let Statement::Subtest(_) = statement {
+///         subtests += 1;
+///     }
+/// }
+/// assert_eq!(subtests, 1);
+/// assert_eq!((&doc).into_iter().count(), 3);
+/// ```
+impl<'d, 'a> IntoIterator for &'d Document<'a> {
+    type Item = &'d Statement<'a>;
+    type IntoIter = slice::Iter<'d, Statement<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.body.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tests_deeply_nested() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..2\n",
+            "ok 1 - first\n",
+            "# Subtest: outer\n",
+            "    1..2\n",
+            "    # Subtest: inner\n",
+            "        1..1\n",
+            "        ok 1 - deep\n",
+            "    ok 1 - inner\n",
+            "    ok 2 - sibling\n",
+            "ok 2 - outer\n",
+        );
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        let tests: Vec<_> = doc
+            .tests_with_depth()
+            .map(|(depth, t)| (depth, t.description.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            tests,
+            [
+                (0, "first"),
+                (2, "deep"),
+                (1, "inner"),
+                (1, "sibling"),
+                (0, "outer"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,183 @@
+//! JUnit XML export, for CI systems that don't consume TAP.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+};
+use core::fmt::Write;
+
+use crate::{validate::resolve_numbers, Document, Key, Plan, Statement};
+
+/// Escape `s` for use in XML text and attribute values.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A line break followed by `indent`.
+fn nl(indent: &str) -> String {
+    format!("\n{}", indent)
+}
+
+/// Append a `<testsuite>` named `name` to `xml`, holding the tests and subtests of `statements`.
+fn write_suite(xml: &mut String, name: &str, plan: &Plan, statements: &[Statement], depth: usize) {
+    let indent = "  ".repeat(depth);
+    let tests = plan.count();
+    let numbers = resolve_numbers(statements);
+    let (mut failures, mut skipped) = (0, 0);
+    for (_, test) in &numbers {
+        match test.directive.as_ref().map(|d| d.key) {
+            Some(Key::Skip | Key::Todo) => skipped += 1,
+            _ if !test.result => failures += 1,
+            _ => {}
+        }
+    }
+    let _ = writeln!(
+        xml,
+        r#"{}<testsuite name="{}" tests="{}" failures="{}" skipped="{}">"#,
+        indent,
+        escape(name),
+        tests,
+        failures,
+        skipped
+    );
+
+    let mut numbers = numbers.iter();
+    for statement in statements {
+        match statement {
+            Statement::Test(test) => {
+                let number = numbers.next().map_or(0, |(n, _)| *n);
+                let description = test.description.as_deref().map(str::trim_end);
+                let name = match description {
+                    Some(description) => format!("{} - {}", number, description),
+                    None => number.to_string(),
+                };
+                let _ = write!(xml, r#"{}  <testcase name="{}""#, indent, escape(&name));
+                let _ = match &test.directive {
+                    Some(directive) if matches!(directive.key, Key::Skip | Key::Todo) => {
+                        let message = match directive.reason {
+                            Some(reason) => format!("{} {}", directive.key, reason),
+                            None => directive.key.to_string(),
+                        };
+                        writeln!(
+                            xml,
+                            r#">{}    <skipped message="{}"/>{}  </testcase>"#,
+                            nl(&indent),
+                            escape(&message),
+                            nl(&indent)
+                        )
+                    }
+                    _ if !test.result => writeln!(
+                        xml,
+                        r#">{}    <failure message="{}">{}</failure>{}  </testcase>"#,
+                        nl(&indent),
+                        escape(description.unwrap_or("not ok")),
+                        escape(&test.yaml.join("\n")),
+                        nl(&indent)
+                    ),
+                    _ => writeln!(xml, "/>"),
+                };
+            }
+            Statement::Subtest(subtest) => write_suite(
+                xml,
+                subtest.name.unwrap_or("subtest"),
+                &subtest.plan,
+                &subtest.body,
+                depth + 1,
+            ),
+            _ => {}
+        }
+    }
+    let _ = writeln!(xml, "{}</testsuite>", indent);
+}
+
+impl Document<'_> {
+    /// Render the document as JUnit XML.
+    ///
+    /// Each [`crate::Test`] becomes a `<testcase>`, each [`crate::Subtest`] a nested `<testsuite>`. A `not ok` test
+    /// holds a `<failure>` embedding its YAML block. Tests with a [`crate::Key::Skip`] or [`crate::Key::Todo`]
+    /// directive are reported as `<skipped>`, as TODO failures are not supposed to fail the run. The `tests` count of
+    /// each suite is driven by its [`Plan`].
+    ///
+    /// Only available with the `junit` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..2\n",
+    ///     "ok 1 - foo()\n",
+    ///     "not ok 2 - bar()\n",
+    ///     "  ---\n",
+    ///     "  message: boom\n",
+    ///     "  ...\n",
+    /// );
+    /// let xml = Document::parse_from_str(content).expect("Parser error").to_junit_xml();
+    /// assert!(xml.contains(r#"<testsuite name="tap" tests="2" failures="1" skipped="0">"#));
+    /// assert!(xml.contains(r#"<testcase name="1 - foo()"/>"#));
+    /// assert!(xml.contains(r#"<failure message="bar()">message: boom</failure>"#));
+    /// ```
+    pub fn to_junit_xml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n");
+        write_suite(&mut xml, "tap", &self.plan, &self.body, 1);
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_junit_nested() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..2\n",
+            "# Subtest: <sub>\n",
+            "    1..2\n",
+            "    ok 1 - inner # SKIP not today\n",
+            "    not ok 2 # TODO later\n",
+            "ok 1 - <sub>\n",
+            "not ok 2 - a & b\n",
+        );
+        let xml = Document::parse_from_str(content)
+            .expect("Parser error")
+            .to_junit_xml();
+        assert_eq!(
+            xml,
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                "<testsuites>\n",
+                "  <testsuite name=\"tap\" tests=\"2\" failures=\"1\" skipped=\"0\">\n",
+                "    <testsuite name=\"&lt;sub&gt;\" tests=\"2\" failures=\"0\" skipped=\"2\">\n",
+                "      <testcase name=\"1 - inner\">\n",
+                "        <skipped message=\"SKIP not today\"/>\n",
+                "      </testcase>\n",
+                "      <testcase name=\"2\">\n",
+                "        <skipped message=\"TODO later\"/>\n",
+                "      </testcase>\n",
+                "    </testsuite>\n",
+                "    <testcase name=\"1 - &lt;sub&gt;\"/>\n",
+                "    <testcase name=\"2 - a &amp; b\">\n",
+                "      <failure message=\"a &amp; b\"></failure>\n",
+                "    </testcase>\n",
+                "  </testsuite>\n",
+                "</testsuites>\n",
+            )
+        );
+    }
+}
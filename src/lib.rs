@@ -1,130 +1,504 @@
+#![no_std]
+
+extern crate alloc;
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
 extern crate pest;
 #[macro_use]
 extern crate pest_derive;
 
-use anyhow::{anyhow, Result};
+use alloc::{
+    borrow::{Cow, ToOwned},
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::convert::TryFrom;
 use pest::{
     iterators::{Pair, Pairs},
-    Parser,
+    Parser, Position,
 };
-use serde::Serialize;
-
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+mod builder;
+mod collect;
+#[cfg(feature = "csv")]
+mod csv;
+mod display;
+mod duration;
+mod error;
+#[cfg(feature = "serde")]
+mod flat;
+#[cfg(feature = "html")]
+mod html;
+mod iter;
+#[cfg(feature = "junit")]
+mod junit;
+mod location;
+mod markdown;
+mod merge;
+mod owned;
+mod parsed;
+mod partial;
+#[cfg(feature = "serde")]
+mod serialize;
+mod stream;
+#[cfg(feature = "subunit")]
+mod subunit;
+mod summary;
+mod validate;
+mod visit;
+mod warning;
+
+use collect::is_indented;
+
+pub use builder::DocumentBuilder;
+pub use display::DisplayOptions;
+pub use error::{Limit, Result, TapError};
+pub use location::Location;
+pub use owned::*;
+pub use parsed::ParsedDocument;
+pub use partial::{classify_line, parse_description, parse_number, LineKind};
+#[cfg(feature = "serde")]
+pub use serialize::{parse_to_json, OmitNull, YamlAsObject};
+pub use stream::{ParserEvent, StreamingParser};
+pub use summary::{SkipFailPolicy, Summary};
+pub use validate::{Mismatch, NumberingIssue, NumberingIssueKind, PlanMismatch, Strictness};
+pub use visit::Visitor;
+pub use warning::{Warning, WarningCategory};
+
+/// The pest parser generated from the TAP grammar, along with its [`Rule`] enum.
+///
+/// Both mirror the grammar, which is an implementation detail: rules may be added, renamed or restructured in any
+/// release. Prefer the `parse_from_str` methods of the parsed types, or [`parse_description`] and [`parse_number`] for
+/// fragments of a line.
 #[derive(Parser)]
 #[grammar = "tap14.pest"]
 pub struct TAPParser;
 
+/// Location of a parsed construct within the source content.
+///
+/// Lines and columns are 1-based, with columns counted in characters. Byte offsets are 0-based, `end` being exclusive.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Span {
+    /// Byte offset of the first character.
+    pub start: usize,
+    /// Byte offset just past the last character.
+    pub end: usize,
+    /// Line of the first character.
+    pub start_line: usize,
+    /// Column of the first character.
+    pub start_col: usize,
+    /// Line of the last character.
+    pub end_line: usize,
+    /// Column just past the last character.
+    pub end_col: usize,
+}
+
+impl Span {
+    /// Locate `pair`, relying on pest's line index of the parsed input. Prefer it over [`Span::from_pest`], which scans
+    /// the input up to the span's start for its line and column.
+    fn from_pair(pair: &Pair<'_, Rule>) -> Self {
+        Self::with_start(pair.as_span(), pair.line_col())
+    }
+
+    pub(crate) fn from_pest(span: pest::Span<'_>) -> Self {
+        Self::with_start(span, span.start_pos().line_col())
+    }
+
+    /// Locate `span`, given the line and column of its start.
+    pub(crate) fn with_start(
+        span: pest::Span<'_>,
+        (start_line, start_col): (usize, usize),
+    ) -> Self {
+        let text = span.as_str();
+        let (end_line, end_col) = match text.rfind('\n') {
+            Some(i) => (
+                start_line + text.matches('\n').count(),
+                text[i + 1..].chars().count() + 1,
+            ),
+            None => (start_line, start_col + text.chars().count()),
+        };
+        Self {
+            start: span.start(),
+            end: span.end(),
+            start_line,
+            start_col,
+            end_line,
+            end_col,
+        }
+    }
+
+    /// Move the span `bytes` and `lines` further into the source, e.g. when it was parsed from a chunk of the source.
+    pub(crate) fn shift(&mut self, bytes: usize, lines: usize) {
+        self.start += bytes;
+        self.end += bytes;
+        self.start_line += lines;
+        self.end_line += lines;
+    }
+}
+
 /// The TAP [`Preamble`] declares the start of a TAP document.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct Preamble<'a> {
     /// TAP specification version. Can be any semantic version string (e.g. `14` or `14.1.3`).
     pub version: &'a str,
 }
 
 /// The [`Plan`] tells how many tests will be run, or how many tests have run.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct Plan<'a> {
-    /// ID of first planned test. _Should_ always start with `1`.
+    /// ID of first planned test, usually `1`. Plans covering a subrange, e.g. `5..10`, are permitted as well, see
+    /// [`Plan::count`].
     pub first: i32,
     /// ID of last planned test. A value of `0` _should_ indicate no tests were executed.
     pub last: i32,
     /// Arbitrary string which _should_ indicate why the certain tests were skipped.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "crate::serialize::is_omitted_none")
+    )]
     pub reason: Option<&'a str>,
 }
 
-/// The body of the TAP document.
-#[derive(Debug, Serialize)]
+/// The body of the TAP document, i.e. a sequence of [`Statement`]s.
+///
+/// [`Document::body`] and [`Subtest::body`] hold their statements as a plain [`Vec`] for convenience. A [`Body`] can be
+/// parsed on its own, or created from such a [`Vec`], to pass a sequence of statements around as a unit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct Body<'a> {
     /// List of [`Statement`]s.
     statements: Vec<Statement<'a>>,
+    /// Location of the [`Body`] in the source.
+    span: Span,
 }
 
 /// A [`Pragma`] provides information to the document parser/interpreter.
 ///
 /// # Note
 ///
-/// Due to the PEG parsing approach, pragmas have no effect.
-#[derive(Debug, Serialize)]
+/// Due to the PEG parsing approach, pragmas have no effect on parsing itself. Only the `strict` pragma is interpreted,
+/// and only if [`ParseOptions::pragmas`] is enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct Pragma<'a> {
     /// If present, declares if the given `option` should be enabled or disabled.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "crate::serialize::is_omitted_none")
+    )]
     pub flag: Option<bool>,
     /// Pragma option identifier.
     pub option: &'a str,
+    /// Location of the [`Pragma`] in the source.
+    pub span: Span,
 }
 
 /// Marks an emergency exit of the test procedure.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct BailOut<'a> {
     /// Optional reason for bailing out of the test procedure.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "crate::serialize::is_omitted_none")
+    )]
     pub reason: Option<&'a str>,
+    /// Location of the [`BailOut`] in the source.
+    pub span: Span,
 }
 
 /// Directive keys supported by [`Directive`].
-#[derive(Debug, Serialize)]
-pub enum Key {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Key<'a> {
     /// Test was skipped
     Skip,
     /// Test has a to-do.
     Todo,
+    /// A nonstandard directive keyword emitted by Automake style producers, either `XFAIL` or `XPASS` in uppercase.
+    /// Other keywords are read as a comment. Rejected by [`ParseOptions::pragmas`] while `pragma +strict` is active.
+    Other(&'a str),
 }
 
 /// A [`Directive`] gives some meta-data about the execution of a [`Test`].
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct Directive<'a> {
     /// A directive key, declaring the nature of this [`Directive`].
-    pub key: Key,
+    pub key: Key<'a>,
     /// A reason why this test was [`Key::Skip`]ped or why it is a [`Key::Todo`].
+    ///
+    /// The reason is trimmed consistently: surrounding whitespace and a single leading `:` are stripped, so
+    /// `# SKIP not supported`, `# SKIP: not supported` and `# SKIP : not supported` all yield `not supported`. An
+    /// empty reason is [`None`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "crate::serialize::is_omitted_none")
+    )]
     pub reason: Option<&'a str>,
+    /// Everything following the key as written in the source, before trimming, e.g. `: not supported`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub raw_reason: Option<&'a str>,
 }
 
 /// A [`Test`] declaring the result of some test-case.
-#[derive(Debug, Serialize)]
+///
+/// Equality and hashing take every field into account, including the [`Test::span`]. To match up the same test line
+/// across several runs, e.g. in a `HashSet`, reset the span to [`Span::default`] first.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use tapconsooomer::{Document, Span};
+///
+/// let runs = ["TAP version 14\n1..1\nnot ok 1 - flaky\n", "TAP version 14\n1..1\n\nnot ok 1 - flaky\n"];
+/// let mut failures = HashMap::new();
+/// for run in runs {
+///     let doc = Document::parse_from_str(run).expect("Parser error");
+///     for test in doc.tests().filter(|t| !t.result) {
+///         let test = tapconsooomer::Test { span: Span::default(), ..test.clone() };
+///         *failures.entry(test).or_insert(0) += 1;
+///     }
+/// }
+/// assert_eq!(failures.len(), 1);
+/// assert_eq!(failures.values().next(), Some(&2));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct Test<'a> {
     /// Result of the test.
     pub result: bool,
-    /// Number of the test.
+    /// Number of the test. Leading zeros are normalized away, e.g. `007` yields `7`. [`None`] if the test has no
+    /// number, or if it doesn't fit into an [`i32`], see [`Test::has_invalid_number`] to tell these apart.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "crate::serialize::is_omitted_none")
+    )]
     pub number: Option<i32>,
-    /// Description of the test.
-    pub description: Option<&'a str>,
+    /// Number of the test exactly as written in the source, e.g. `007`. Used to re-emit the original form.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub raw_number: Option<&'a str>,
+    /// Position of the test within its plan scope, i.e. its document or subtest. This is the explicit
+    /// [`Test::number`] if given, or else one past the position of the preceding test in the same scope, starting at
+    /// `1`. Thus, an explicit number resets the running count, e.g. `ok`, `ok 5`, `ok` yields `1`, `5`, `6`.
+    pub effective_number: i32,
+    /// Description of the test, without any whitespace separating it from a following directive or comment. A `\#`
+    /// escaping a literal hash is unescaped to `#`, which is the only case requiring an allocation.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "crate::serialize::is_omitted_none")
+    )]
+    pub description: Option<Cow<'a, str>>,
+    /// Whether the description is separated from the number by the canonical `-`, e.g. `ok 1 - foo` rather than
+    /// `ok 1 foo`. Only the first `-` is taken as separator, so `ok 1 - - foo` yields the description `- foo`. Always
+    /// `false` without a description. Used to re-emit the producer's choice.
+    pub dash: bool,
     /// Directive detailing this tests meta-execution.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "crate::serialize::is_omitted_none")
+    )]
     pub directive: Option<Directive<'a>>,
+    /// Metadata attached by `# @key: value` comments directly preceding the test. Only populated if
+    /// [`ParseOptions::comment_metadata`] is enabled.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "BTreeMap::is_empty"))]
+    pub metadata: BTreeMap<&'a str, &'a str>,
     /// List of YAML lines detailing the test execution.
+    ///
+    /// The block ends at the first line consisting solely of `...` at the block's anchor indentation. Deeper indented
+    /// `...` lines, or `...` followed by other text, are kept as YAML content.
     pub yaml: Yaml<'a>,
+    /// The YAML block exactly as written in the source, from the `---` marker up to and including the `...` marker.
+    /// Unlike [`Test::yaml`], it retains the original indentation of every line. Used for exact reproduction, or to
+    /// hand the block to a stricter YAML validator.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub yaml_raw: Option<&'a str>,
+    /// Location of the [`Test`] in the source, including its YAML block.
+    pub span: Span,
 }
 
 /// [`Subtest`]s provide a way to nest one TAP14 stream inside another. This may be used in a variety of ways, depending on
 /// the test harness.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct Subtest<'a> {
     /// Name of the subtest, declared by a comment at the start of the [`Subtest`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "crate::serialize::is_omitted_none")
+    )]
     pub name: Option<&'a str>,
+    /// The comment declaring the [`Subtest`] exactly as written in the source, without indentation, e.g.
+    /// `# Subtest:foo`. Used to re-emit the original form, as long as the name it declares still equals
+    /// [`Subtest::name`].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub raw_declaration: Option<&'a str>,
     /// The [`Plan`] of the [`Subtest`].
     pub plan: Plan<'a>,
     /// Main [`Body`] of the [`Subtest`].
     pub body: Vec<Statement<'a>>,
+    /// Location of the [`Subtest`] in the source, starting at its declaring comment if present. Slicing the source by
+    /// its byte offsets reproduces the subtest verbatim, including any comments dropped while parsing.
+    pub span: Span,
 }
 
 /// An enumeration of all possible TAP constructs that can be part of a [`Body`].
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub enum Statement<'a> {
     /// Any text not captured by another [`Statement`] variant.
-    #[serde(rename = "anything")]
+    #[cfg_attr(feature = "serde", serde(rename = "anything"))]
     Anything(&'a str),
+    /// The text of a standalone comment line, without the leading `#`. Only preserved if [`ParseOptions::comments`] is
+    /// enabled. Comments declaring a [`Subtest::name`] are never captured as [`Statement::Comment`].
+    #[cfg_attr(feature = "serde", serde(rename = "comment"))]
+    Comment(&'a str),
     /// A [`BailOut`] statement.
-    #[serde(rename = "bail_out")]
+    #[cfg_attr(feature = "serde", serde(rename = "bail_out"))]
     BailOut(BailOut<'a>),
     /// A [`Pragma`] statement.
-    #[serde(rename = "pragma")]
+    #[cfg_attr(feature = "serde", serde(rename = "pragma"))]
     Pragma(Pragma<'a>),
     /// A [`Subtest`] statement.
-    #[serde(rename = "subtest")]
+    #[cfg_attr(feature = "serde", serde(rename = "subtest"))]
     Subtest(Subtest<'a>),
     /// A [`Test`] statement.
-    #[serde(rename = "test")]
+    #[cfg_attr(feature = "serde", serde(rename = "test"))]
     Test(Test<'a>),
 }
 
+/// Options controlling optional parser behavior. The [`Default`] options match [`Document::parse_from_str`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ParseOptions {
+    /// Attach `# @key: value` comments to the [`Test`] following them, see [`Test::metadata`].
+    pub comment_metadata: bool,
+    /// Interpret `pragma +strict` and `pragma -strict`. While `strict` is active, any [`Statement::Anything`] fails
+    /// parsing with [`TapError::NonTapLine`], any [`Key::Other`] directive with [`TapError::InvalidDirectiveKey`], and
+    /// any test number out of range with [`TapError::NumberParse`]. Each [`Subtest`] starts out non-strict, regardless
+    /// of its parent.
+    pub pragmas: bool,
+    /// Preserve standalone comment lines as [`Statement::Comment`].
+    pub comments: bool,
+    /// Stop collecting top-level [`Statement`]s at the first top-level [`BailOut`], as anything following it is
+    /// undefined. The remaining lines of the body are recorded verbatim as a single, trailing [`Statement::Anything`],
+    /// which is exempt from [`ParseOptions::pragmas`].
+    pub stop_at_bail_out: bool,
+    /// Accept documents lacking the `TAP version` preamble, as emitted by producers predating TAP13. A missing
+    /// preamble defaults [`Preamble::version`] to `13`.
+    pub implicit_preamble: bool,
+    /// Maximum number of nested indentation levels, as introduced by each [`Subtest`] and YAML block. Deeper content
+    /// fails parsing with [`TapError::MaxDepthExceeded`] up front, instead of overflowing the stack of the recursive
+    /// parser on untrusted input. Defaults to [`ParseOptions::DEFAULT_MAX_DEPTH`].
+    pub max_depth: usize,
+    /// Maximum size of the content in bytes. Larger content fails parsing with [`TapError::LimitExceeded`] up front,
+    /// e.g. to protect a service parsing untrusted output of a runaway producer. This is what bounds the memory used
+    /// for parsing. [`OwnedDocument::parse_from_reader_with_options`] stops reading past the limit. Unlimited by
+    /// default.
+    pub max_bytes: Option<usize>,
+    /// Maximum number of [`Statement`]s, counting those nested inside [`Subtest`]s as well. More statements fail
+    /// parsing with [`TapError::LimitExceeded`]. The statements are counted once they have been built, so this limits
+    /// the size of the resulting [`Document`], but not the memory used while parsing, see [`ParseOptions::max_bytes`]
+    /// for that. Unlimited by default.
+    pub max_statements: Option<usize>,
+    /// Require the plan to be the first or the last line of its body, as TAP14 demands, failing with
+    /// [`TapError::MisplacedPlan`] otherwise. By default, a top-level body ends at its plan, ignoring any tests
+    /// following it, see [`Document::parse_exact`], while the lines following a trailing [`Subtest`] plan are kept
+    /// as [`Statement::Anything`].
+    pub strict_plan_position: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            comment_metadata: false,
+            pragmas: false,
+            comments: false,
+            stop_at_bail_out: false,
+            implicit_preamble: false,
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+            max_bytes: None,
+            max_statements: None,
+            strict_plan_position: false,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Default of [`ParseOptions::max_depth`]. Also applies to the `parse_from_str` functions not taking any options.
+    pub const DEFAULT_MAX_DEPTH: usize = 64;
+
+    /// Create the [`Default`] options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set [`ParseOptions::comment_metadata`].
+    pub fn comment_metadata(mut self, enabled: bool) -> Self {
+        self.comment_metadata = enabled;
+        self
+    }
+
+    /// Set [`ParseOptions::comments`].
+    pub fn comments(mut self, enabled: bool) -> Self {
+        self.comments = enabled;
+        self
+    }
+
+    /// Set [`ParseOptions::pragmas`].
+    pub fn pragmas(mut self, enabled: bool) -> Self {
+        self.pragmas = enabled;
+        self
+    }
+
+    /// Set [`ParseOptions::stop_at_bail_out`].
+    pub fn stop_at_bail_out(mut self, enabled: bool) -> Self {
+        self.stop_at_bail_out = enabled;
+        self
+    }
+
+    /// Set [`ParseOptions::implicit_preamble`].
+    pub fn implicit_preamble(mut self, enabled: bool) -> Self {
+        self.implicit_preamble = enabled;
+        self
+    }
+
+    /// Set [`ParseOptions::max_depth`].
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Set [`ParseOptions::max_bytes`].
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Set [`ParseOptions::max_statements`].
+    pub fn max_statements(mut self, max_statements: usize) -> Self {
+        self.max_statements = Some(max_statements);
+        self
+    }
+
+    /// Set [`ParseOptions::strict_plan_position`].
+    pub fn strict_plan_position(mut self, enabled: bool) -> Self {
+        self.strict_plan_position = enabled;
+        self
+    }
+}
+
 /// A [`Document`] represents the root of any TAP document. It's the main point of interaction for users of this API.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct Document<'a> {
     /// The document's preamble.
     pub preamble: Preamble<'a>,
@@ -133,6 +507,10 @@ pub struct Document<'a> {
     /// The document's top-level [`Body`] as a collection of [`Statement`]s. Some [`Statement`]s, like [`Subtest`] may
     /// declare _nested_ [`Body`]s.
     pub body: Vec<Statement<'a>>,
+    /// Location of the [`Document`] in the source, from its preamble to the end of its plan or body, whichever comes
+    /// last. Slicing the source by its byte offsets reproduces the document verbatim, including any comments dropped
+    /// while parsing.
+    pub span: Span,
 }
 
 #[derive(Debug)]
@@ -141,15 +519,19 @@ enum DocumentContent<'a> {
     Body(Vec<Statement<'a>>),
 }
 
-#[derive(Debug)]
-enum SubtestContent<'a> {
-    Plan(Plan<'a>),
-    Statement(Statement<'a>),
-}
-
 type Yaml<'a> = Vec<&'a str>;
 
 impl<'a> Preamble<'a> {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            target = "tap",
+            level = "trace",
+            name = "preamble",
+            skip_all,
+            fields(line = pairs.peek().map(|p| p.line_col().0)),
+        )
+    )]
     fn parse(mut pairs: Pairs<'a, Rule>) -> Self {
         Self {
             version: pairs.next().unwrap().as_str(),
@@ -184,16 +566,78 @@ impl<'a> Preamble<'a> {
             .next()
             .map(Pair::into_inner)
             .map(Self::parse)
-            .ok_or_else(|| anyhow!("Can't parse '{}'", content))
+            .ok_or(TapError::Empty)
+    }
+
+    /// Split [`Preamble::version`] into its major, minor and patch components, e.g. to tell TAP13 and TAP14 apart.
+    /// Components beyond the patch version are ignored. Yields [`None`] if any component isn't a number fitting into
+    /// a [`u32`], which can't happen for a parsed preamble short of overflow.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Preamble;
+    ///
+    /// let preamble = Preamble::parse_from_str("TAP version 14").expect("Parser error");
+    /// assert_eq!(preamble.version_parts(), Some((14, None, None)));
+    ///
+    /// let preamble = Preamble::parse_from_str("TAP version 13.1").expect("Parser error");
+    /// assert_eq!(preamble.version_parts(), Some((13, Some(1), None)));
+    ///
+    /// assert_eq!(Preamble { version: "14.1.3" }.version_parts(), Some((14, Some(1), Some(3))));
+    /// assert_eq!(Preamble { version: "fourteen" }.version_parts(), None);
+    /// ```
+    pub fn version_parts(&self) -> Option<(u32, Option<u32>, Option<u32>)> {
+        let mut parts = self.version.split('.');
+        let mut next = || parts.next().map(str::parse::<u32>).transpose().ok();
+        let major = next()??;
+        Some((major, next()?, next()?))
+    }
+}
+
+/// An empty plan, `1..0`, as declared by a document running no tests at all.
+impl Default for Plan<'_> {
+    fn default() -> Self {
+        Self::new(1, 0)
     }
 }
 
 impl<'a> Plan<'a> {
+    /// Create a [`Plan`] of the tests `first..last`, without a reason.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Plan;
+    ///
+    /// let plan = Plan::new(1, 3);
+    /// assert_eq!(plan.to_string(), "1..3");
+    /// assert_eq!(Plan::default().to_string(), "1..0");
+    /// ```
+    pub fn new(first: i32, last: i32) -> Self {
+        Self {
+            first,
+            last,
+            reason: None,
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            target = "tap",
+            level = "trace",
+            name = "plan",
+            skip_all,
+            fields(line = pairs.peek().map(|p| p.line_col().0)),
+            err(level = "debug", Display)
+        )
+    )]
     fn parse(mut pairs: Pairs<'a, Rule>) -> Result<Self> {
         Ok(Self {
             first: pairs.next().unwrap().as_str().parse()?,
             last: pairs.next().unwrap().as_str().parse()?,
-            reason: pairs.next().map(|r| r.as_str()),
+            reason: pairs.next().and_then(|r| trim_trailing(r.as_str())),
         })
     }
 
@@ -229,20 +673,69 @@ impl<'a> Plan<'a> {
             .next()
             .map(Pair::into_inner)
             .map(Self::parse)
-            .ok_or_else(|| anyhow!("Can't parse '{}'", content))?
+            .ok_or(TapError::Empty)?
+    }
+
+    /// Whether this is a skip-all plan (`1..0`), declaring that all tests were skipped and none will follow.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Plan;
+    ///
+    /// assert!(Plan::parse_from_str("1..0 # SKIP no network").expect("Parser error").is_skip_all());
+    /// assert!(!Plan::parse_from_str("1..1").expect("Parser error").is_skip_all());
+    /// ```
+    pub fn is_skip_all(&self) -> bool {
+        self.first == 1 && self.last == 0
+    }
+
+    /// Number of tests announced by the plan, i.e. `last - first + 1`. Subranges count just the tests they cover,
+    /// e.g. `3` for `5..7`. Yields `0` for a skip-all plan, or any other plan whose `last` precedes its `first`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Plan;
+    ///
+    /// assert_eq!(Plan::parse_from_str("1..4").expect("Parser error").count(), 4);
+    /// assert_eq!(Plan::parse_from_str("5..10").expect("Parser error").count(), 6);
+    /// assert_eq!(Plan::parse_from_str("1..0").expect("Parser error").count(), 0);
+    /// ```
+    pub fn count(&self) -> usize {
+        usize::try_from(i64::from(self.last) - i64::from(self.first) + 1).unwrap_or(0)
+    }
+
+    /// The reason of a skip-all plan, without a leading `SKIP` keyword and trimmed like [`Directive::reason`].
+    pub(crate) fn skip_reason(&self) -> Option<&'a str> {
+        let reason = self.reason?.trim_start();
+        let reason = match reason.get(..4) {
+            Some(key) if key.eq_ignore_ascii_case("skip") => &reason[4..],
+            _ => reason,
+        };
+        trim_reason(reason)
     }
 }
 
 impl<'a> Directive<'a> {
-    fn parse(mut pairs: Pairs<'a, Rule>) -> Result<Self> {
-        let key = pairs.next().unwrap().as_str().to_lowercase();
+    fn parse(pair: Pair<'a, Rule>) -> Result<Self> {
+        let directive = pair.as_str();
+        let start = pair.as_span().start();
+        /* TAP keywords are ASCII, so there's no need for allocating, Unicode-aware case folding. */
+        let key = pair.into_inner().next().unwrap();
+        /* The raw reason includes the separating whitespace and colon, which the `reason` token doesn't. */
+        let raw_reason = Some(&directive[key.as_span().end() - start..]).filter(|r| !r.is_empty());
+        let key = key.as_str();
         Ok(Self {
-            key: match key.as_str() {
-                "skip" => Ok(Key::Skip),
-                "todo" => Ok(Key::Todo),
-                _ => Err(anyhow!("Directive key '{}' must be 'skip' or 'todo'", key)),
-            }?,
-            reason: pairs.next().map(|p| p.as_str()),
+            key: if key.eq_ignore_ascii_case("skip") {
+                Key::Skip
+            } else if key.eq_ignore_ascii_case("todo") {
+                Key::Todo
+            } else {
+                Key::Other(key)
+            },
+            reason: raw_reason.and_then(trim_reason),
+            raw_reason,
         })
     }
 
@@ -276,33 +769,71 @@ impl<'a> Directive<'a> {
     pub fn parse_from_str(content: &'a str) -> Result<Self> {
         TAPParser::parse(Rule::directive, content)?
             .next()
-            .map(Pair::into_inner)
             .map(Self::parse)
-            .ok_or_else(|| anyhow!("Can't parse '{}'", content))?
+            .ok_or(TapError::Empty)?
+    }
+}
+
+/// Strip surrounding whitespace and a single leading `:` from a raw directive reason. Returns [`None`] if nothing is
+/// left.
+fn trim_reason(raw: &str) -> Option<&str> {
+    let reason = raw.trim_matches([' ', '\t']);
+    let reason = reason
+        .strip_prefix(':')
+        .unwrap_or(reason)
+        .trim_matches([' ', '\t']);
+    (!reason.is_empty()).then_some(reason)
+}
+
+/// Strip trailing spaces and tabs, which the grammar captures along with free-form text preceding a `#` or the end of
+/// the line. Returns [`None`] if nothing is left.
+fn trim_trailing(raw: &str) -> Option<&str> {
+    let trimmed = raw.trim_end_matches([' ', '\t']);
+    (!trimmed.is_empty()).then_some(trimmed)
+}
+
+/// Replace each `\#` in `s` by a literal `#`, only allocating if there is anything to replace.
+fn unescape_hash(s: &str) -> Cow<'_, str> {
+    if s.contains("\\#") {
+        Cow::Owned(s.replace("\\#", "#"))
+    } else {
+        Cow::Borrowed(s)
     }
 }
 
 impl<'a> Test<'a> {
-    fn parse(mut pairs: Pairs<'a, Rule>) -> Result<Self> {
+    fn parse(pair: Pair<'a, Rule>) -> Result<Self> {
+        let span = Span::from_pair(&pair);
+        let mut pairs = pair.into_inner();
         let pair = pairs.next().unwrap();
-        let result = match pair.as_str().to_lowercase().as_str() {
-            "ok" => Ok(true),
-            "not ok" => Ok(false),
-            _ => Err(anyhow!(
-                "Result '{}' must be 'ok' or 'not ok'",
-                pair.as_str()
-            )),
+        let result = match pair.as_str() {
+            r if r.eq_ignore_ascii_case("ok") => Ok(true),
+            r if r.eq_ignore_ascii_case("not ok") => Ok(false),
+            r => Err(TapError::InvalidResult(r.to_owned())),
         }?;
+        /* End of the result or number, whichever precedes the description. */
+        let mut end = pair.as_span().end();
         let mut number: Option<i32> = None;
+        let mut raw_number = None;
         let mut description = None;
+        let mut dash = false;
         let mut directive = None;
         let mut yaml = Vec::new();
+        let mut yaml_raw = None;
         for pair in pairs {
             match pair.as_rule() {
-                Rule::number => number = pair.as_str().parse::<i32>().ok(),
-                Rule::description => description = Some(pair.as_str()),
-                Rule::directive => directive = Directive::parse(pair.into_inner()).ok(),
+                Rule::number => {
+                    raw_number = Some(pair.as_str());
+                    number = pair.as_str().parse::<i32>().ok();
+                    end = pair.as_span().end();
+                }
+                Rule::description => {
+                    dash = pair.get_input()[end..pair.as_span().start()].contains('-');
+                    description = trim_trailing(pair.as_str()).map(unescape_hash);
+                }
+                Rule::directive => directive = Directive::parse(pair).ok(),
                 Rule::yaml_block => {
+                    yaml_raw = Some(pair.as_str().trim_matches([' ', '\t']));
                     yaml.append(&mut { pair.into_inner().map(|p| p.as_str()).collect() })
                 }
                 _ => unreachable!(),
@@ -311,9 +842,15 @@ impl<'a> Test<'a> {
         Ok(Self {
             result,
             number,
+            raw_number,
+            effective_number: number.unwrap_or(1),
             description,
+            dash,
             directive,
+            metadata: BTreeMap::new(),
             yaml,
+            yaml_raw,
+            span,
         })
     }
 
@@ -330,11 +867,22 @@ impl<'a> Test<'a> {
     /// let test = Test::parse_from_str(content).expect("Parser error");
     /// assert_eq!(test.result, false);
     /// assert_eq!(test.number, Some(1));
-    /// assert_eq!(test.description, Some("foo()"));
+    /// assert_eq!(test.description.as_deref(), Some("foo()"));
     /// assert_eq!(test.directive.is_none(), true);
     /// assert_eq!(test.yaml.len(), 0);
     /// ```
     ///
+    /// Whitespace separating the description from a directive is not part of either:
+    ///
+    /// ```
+    /// use tapconsooomer::Test;
+    ///
+    /// let content = "ok 1 - hello world \t # SKIP not today \t";
+    /// let test = Test::parse_from_str(content).expect("Parser error");
+    /// assert_eq!(test.description.as_deref(), Some("hello world"));
+    /// assert_eq!(test.directive.and_then(|d| d.reason), Some("not today"));
+    /// ```
+    ///
     /// Note, many attributes of a TAP test are optional. A minimal TAP test may look like this:
     ///
     /// ```
@@ -349,6 +897,17 @@ impl<'a> Test<'a> {
     /// assert_eq!(test.yaml.len(), 0);
     /// ```
     ///
+    /// Leniently, the test number may be glued to the result keyword, as emitted by some terse producers:
+    ///
+    /// ```
+    /// use tapconsooomer::Test;
+    ///
+    /// let content = "OK1";
+    /// let test = Test::parse_from_str(content).expect("Parser error");
+    /// assert_eq!(test.result, true);
+    /// assert_eq!(test.number, Some(1));
+    /// ```
+    ///
     /// TAP tests may also optionally contain a YAML block. While no parsing of actual YAML syntax is performed, the
     /// parser captures each line inside the YAML block in a [`Vec`]:
     ///
@@ -365,24 +924,127 @@ impl<'a> Test<'a> {
     /// let test = Test::parse_from_str(content).expect("Parser error");
     /// assert_eq!(test.result, false);
     /// assert_eq!(test.number, Some(2));
-    /// assert_eq!(test.description, Some("bar()"));
+    /// assert_eq!(test.description.as_deref(), Some("bar()"));
     /// assert_eq!(test.directive.is_none(), true);
     /// assert_eq!(test.yaml.len(), 2);
-    /// assert_eq!(test.yaml, ["message: invalid input", "status: failed"])
+    /// assert_eq!(test.yaml, ["message: invalid input", "status: failed"]);
+    /// assert_eq!(
+    ///     test.yaml_raw,
+    ///     Some("---\n  message: invalid input\n  status: failed\n  ...")
+    /// );
+    /// ```
+    ///
+    /// Every [`Test`] records its [`Span`] in the source:
+    ///
+    /// ```
+    /// use tapconsooomer::Test;
+    ///
+    /// let test = Test::parse_from_str("ok 1 - foo()").expect("Parser error");
+    /// assert_eq!((test.span.start_line, test.span.start_col), (1, 1));
+    /// assert_eq!((test.span.end_line, test.span.end_col), (1, 13));
+    /// assert_eq!((test.span.start, test.span.end), (0, 12));
     /// ```
     pub fn parse_from_str(content: &'a str) -> Result<Self> {
         TAPParser::parse(Rule::test, content)?
             .next()
-            .map(Pair::into_inner)
             .map(Self::parse)
-            .ok_or_else(|| anyhow!("Can't parse '{}'", content))?
+            .ok_or(TapError::Empty)?
+    }
+
+    /// Whether the test declares a number that couldn't be parsed, because it doesn't fit into an [`i32`]. Such a
+    /// number is kept in [`Test::raw_number`], whereas [`Test::number`] is [`None`] as if no number had been given.
+    /// While `pragma +strict` is active, [`ParseOptions::pragmas`] rejects it with [`TapError::NumberParse`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Test;
+    ///
+    /// let test = Test::parse_from_str("ok 99999999999 - foo()").expect("Parser error");
+    /// assert_eq!(test.number, None);
+    /// assert_eq!(test.raw_number, Some("99999999999"));
+    /// assert!(test.has_invalid_number());
+    ///
+    /// assert!(!Test::parse_from_str("ok - foo()").unwrap().has_invalid_number());
+    /// ```
+    pub fn has_invalid_number(&self) -> bool {
+        self.number.is_none() && self.raw_number.is_some()
+    }
+
+    /// Whether the test is `not ok` despite a [`Key::Skip`] directive. Harnesses disagree on whether such a test
+    /// failed or was skipped, see [`SkipFailPolicy`] for how [`Document::summary`] counts it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Test;
+    ///
+    /// assert!(Test::parse_from_str("not ok 1 # SKIP no network").unwrap().is_skipped_failure());
+    /// assert!(!Test::parse_from_str("ok 1 # SKIP no network").unwrap().is_skipped_failure());
+    /// assert!(!Test::parse_from_str("not ok 1 # TODO later").unwrap().is_skipped_failure());
+    /// ```
+    pub fn is_skipped_failure(&self) -> bool {
+        !self.result && matches!(&self.directive, Some(d) if d.key == Key::Skip)
+    }
+
+    /// Parse the captured YAML block into a structured [`serde_yaml::Value`].
+    ///
+    /// The lines are joined and any indentation common to all of them is stripped before parsing. A [`Test`] without
+    /// a YAML block yields [`serde_yaml::Value::Null`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Test;
+    ///
+    /// let content = concat!(
+    ///     "not ok 1 - Resolve address\n",
+    ///     "  ---\n",
+    ///     "  message: hostname not found\n",
+    ///     "  severity: fail\n",
+    ///     "  at:\n",
+    ///     "    file: test/dns-resolve.c\n",
+    ///     "    line: 142\n",
+    ///     "  ...\n",
+    /// );
+    /// let test = Test::parse_from_str(content).expect("Parser error");
+    /// let yaml = test.yaml_value().expect("YAML error");
+    /// assert_eq!(yaml["severity"], "fail");
+    /// assert_eq!(yaml["at"]["line"], 142);
+    ///
+    /// let test = Test::parse_from_str("ok 2").expect("Parser error");
+    /// assert!(test.yaml_value().expect("YAML error").is_null());
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn yaml_value(&self) -> Result<serde_yaml::Value> {
+        parse_yaml(&self.yaml)
     }
 }
 
+/// Join YAML `lines`, strip any indentation common to all of them and parse the result.
+#[cfg(feature = "serde")]
+fn parse_yaml<L: AsRef<str>>(lines: &[L]) -> Result<serde_yaml::Value> {
+    let indent = lines
+        .iter()
+        .map(|l| l.as_ref().len() - l.as_ref().trim_start_matches([' ', '\t']).len())
+        .min()
+        .unwrap_or(0);
+    let joined = lines
+        .iter()
+        .map(|l| &l.as_ref()[indent..])
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(serde_yaml::from_str(&joined)?)
+}
+
 impl<'a> BailOut<'a> {
-    fn parse(mut pairs: Pairs<'a, Rule>) -> Result<Self> {
+    fn parse(pair: Pair<'a, Rule>) -> Result<Self> {
         Ok(Self {
-            reason: pairs.next().map(|p| p.as_str()),
+            span: Span::from_pair(&pair),
+            reason: pair
+                .into_inner()
+                .next()
+                .and_then(|p| trim_trailing(p.as_str())),
         })
     }
 
@@ -412,18 +1074,18 @@ impl<'a> BailOut<'a> {
     pub fn parse_from_str(content: &'a str) -> Result<Self> {
         TAPParser::parse(Rule::bail_out, content)?
             .next()
-            .map(Pair::into_inner)
             .map(Self::parse)
-            .ok_or_else(|| anyhow!("Can't parse '{}'", content))?
+            .ok_or(TapError::Empty)?
     }
 }
 
 impl<'a> Pragma<'a> {
-    pub fn parse(mut pairs: Pairs<'a, Rule>) -> Result<Self> {
+    pub fn parse(pair: Pair<'a, Rule>) -> Result<Self> {
+        let span = Span::from_pair(&pair);
+        let mut pairs = pair.into_inner();
         let mut pair = pairs.next().unwrap();
         let flag = match pair.as_rule() {
             Rule::flag => {
-                println!("{}", pair.as_str());
                 let result = match pair.as_str() {
                     "+" => Some(true),
                     "-" => Some(false),
@@ -437,6 +1099,7 @@ impl<'a> Pragma<'a> {
         Ok(Self {
             flag,
             option: pair.as_str(),
+            span,
         })
     }
 
@@ -468,61 +1131,48 @@ impl<'a> Pragma<'a> {
     pub fn parse_from_str(content: &'a str) -> Result<Self> {
         TAPParser::parse(Rule::pragma, content)?
             .next()
-            .map(Pair::into_inner)
             .map(Self::parse)
-            .ok_or_else(|| anyhow!("Can't parse '{}'", content))?
+            .ok_or(TapError::Empty)?
     }
 }
 
 impl<'a> Subtest<'a> {
-    pub fn parse(mut pairs: Pairs<'a, Rule>) -> Result<Self> {
-        let pair = pairs.next().unwrap();
-        let name = match pair.as_rule() {
-            Rule::name => Some(pair.as_str()),
+    pub fn parse(pair: Pair<'a, Rule>) -> Result<Self> {
+        let span = Span::from_pair(&pair);
+        let raw_declaration = Some(pair.as_str())
+            .filter(|raw| raw.starts_with('#'))
+            .and_then(|raw| raw.split(['\r', '\n']).next());
+        let mut pairs = pair.into_inner();
+        let name = match pairs.peek() {
+            Some(p) if p.as_rule() == Rule::name => {
+                pairs.next().and_then(|p| trim_trailing(p.as_str()))
+            }
             _ => None,
         };
 
-        let mut contents = vec![]; /* FIXME: There has to be a better way to split plan and body! */
-
-        /* Consume first pair if not consumed by 'name'. */
-        if name.is_none() {
-            contents.push(match pair.as_rule() {
-                Rule::plan => Plan::parse(pair.into_inner()).map(SubtestContent::Plan),
-                _ => Statement::parse(pair).map(SubtestContent::Statement),
-            }?)
-        }
-
-        /* Now consume the rest of the pairs. */
-        let mut rest: Vec<SubtestContent> = pairs
-            .map(|p| match p.as_rule() {
-                Rule::plan => Plan::parse(p.into_inner()).map(SubtestContent::Plan),
-                _ => Statement::parse(p).map(SubtestContent::Statement),
-            })
-            .collect::<Result<Vec<SubtestContent>>>()?;
-        contents.append(&mut rest);
-
-        let mut p = None;
-        let mut i = 0;
-        while i < contents.len() {
-            /* TODO: Use drain_filter once stable. */
-            if matches!(contents[i], SubtestContent::Plan(_)) {
-                p = Some(match contents.remove(i) {
-                    SubtestContent::Plan(p) => p,
-                    SubtestContent::Statement(_) => unreachable!(),
-                })
+        let mut plan = None;
+        let mut body = Vec::new();
+        for pair in pairs {
+            match pair.as_rule() {
+                Rule::plan => {
+                    let line = pair.line_col().0;
+                    if plan.replace(Plan::parse(pair.into_inner())?).is_some() {
+                        return Err(TapError::MultiplePlans(line));
+                    }
+                }
+                _ => body.push(Statement::parse(pair)?),
             }
-            i += 1;
         }
-        let plan = p.unwrap();
-        let body = contents
-            .into_iter()
-            .filter_map(|s| match s {
-                SubtestContent::Plan(_) => None,
-                SubtestContent::Statement(s) => Some(s),
-            })
-            .collect();
+        let plan = plan.ok_or(TapError::MissingPlan(span.start_line))?;
+        number_tests(&mut body);
 
-        Ok(Self { name, plan, body })
+        Ok(Self {
+            name,
+            raw_declaration,
+            plan,
+            body,
+            span,
+        })
     }
 
     /// Parse [`Subtest`] from a `&str`.
@@ -582,22 +1232,54 @@ impl<'a> Subtest<'a> {
     /// assert_eq!(subtest.body.len(), 1);
     /// ```
     pub fn parse_from_str(content: &'a str) -> Result<Self> {
-        TAPParser::parse(Rule::subtest, content)?
+        check_depth(content, ParseOptions::DEFAULT_MAX_DEPTH)?;
+        let mut subtest = TAPParser::parse(Rule::subtest, content)?
             .next()
-            .map(Pair::into_inner)
             .map(Self::parse)
-            .ok_or_else(|| anyhow!("Can't parse '{}'", content))?
+            .ok_or(TapError::Empty)??;
+        strip_comments(&mut subtest.body);
+        Ok(subtest)
     }
 }
 
 impl<'a> Statement<'a> {
+    /// Move the spans of this statement, including nested ones, see [`Span::shift`].
+    pub(crate) fn shift_spans(&mut self, bytes: usize, lines: usize) {
+        match self {
+            Self::Anything(_) | Self::Comment(_) => {}
+            Self::BailOut(b) => b.span.shift(bytes, lines),
+            Self::Pragma(p) => p.span.shift(bytes, lines),
+            Self::Test(t) => t.span.shift(bytes, lines),
+            Self::Subtest(s) => {
+                s.span.shift(bytes, lines);
+                for statement in &mut s.body {
+                    statement.shift_spans(bytes, lines);
+                }
+            }
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            target = "tap",
+            level = "trace",
+            name = "statement",
+            skip_all,
+            fields(rule = ?pair.as_rule(), line = pair.line_col().0),
+            err(level = "debug", Display)
+        )
+    )]
     pub fn parse(pair: Pair<'a, Rule>) -> Result<Self> {
         match pair.as_rule() {
-            Rule::test => Ok(Self::Test(Test::parse(pair.into_inner())?)),
-            Rule::bail_out => Ok(Self::BailOut(BailOut::parse(pair.into_inner())?)),
-            Rule::pragma => Ok(Self::Pragma(Pragma::parse(pair.into_inner())?)),
-            Rule::subtest => Ok(Self::Subtest(Subtest::parse(pair.into_inner())?)),
+            Rule::test => Ok(Self::Test(Test::parse(pair)?)),
+            Rule::bail_out => Ok(Self::BailOut(BailOut::parse(pair)?)),
+            Rule::pragma => Ok(Self::Pragma(Pragma::parse(pair)?)),
+            Rule::subtest => Ok(Self::Subtest(Subtest::parse(pair)?)),
             Rule::anything => Ok(Self::Anything(pair.as_str())),
+            Rule::comment => Ok(Self::Comment(
+                pair.into_inner().next().map_or("", |p| p.as_str()),
+            )),
             _ => unreachable!(),
         }
     }
@@ -634,52 +1316,240 @@ impl<'a> Statement<'a> {
     /// let stmt = Statement::parse_from_str(subtest).expect("Parser error");
     /// assert!(matches!(stmt, Statement::Subtest(..)));
     /// ```
+    ///
+    /// Each [`Statement`] serializes on its own, externally tagged by its variant. This is handy for emitting one JSON
+    /// object per line (NDJSON):
+    ///
+    /// ```
+    /// # #[cfg(feature = "serde")] {
+    /// use tapconsooomer::Statement;
+    ///
+    /// let stmt = Statement::parse_from_str("hello world").expect("Parser error");
+    /// let json = serde_json::to_string(&stmt).expect("Serializer error");
+    /// assert_eq!(json, r#"{"anything":"hello world"}"#);
+    /// # }
+    /// ```
     pub fn parse_from_str(content: &'a str) -> Result<Self> {
-        TAPParser::parse(Rule::statement, content)?
+        check_depth(content, ParseOptions::DEFAULT_MAX_DEPTH)?;
+        let mut statement = TAPParser::parse(Rule::statement, content)?
             .next()
             .map(Self::parse)
-            .ok_or_else(|| anyhow!("Can't parse '{}'", content))?
-    }
-}
-
-impl<'a> DocumentContent<'a> {
-    fn parse(pair: Pair<'a, Rule>) -> Result<Self> {
-        Ok(match pair.as_rule() {
-            Rule::plan => Self::Plan(Plan::parse(pair.into_inner())?),
-            Rule::body => {
-                let statements: Result<Vec<_>> = pair.into_inner().map(Statement::parse).collect();
-                Self::Body(statements?)
-            }
-            _ => unreachable!(),
-        })
-    }
-}
-
-impl<'a> Document<'a> {
-    pub fn parse(mut pairs: Pairs<'a, Rule>) -> Result<Self> {
-        let preamble = Preamble::parse(pairs.next().unwrap().into_inner());
-
-        let content1 = DocumentContent::parse(pairs.next().unwrap())?;
-        let content2 = DocumentContent::parse(pairs.next().unwrap())?;
-        let (plan, body) = match (content1, content2) {
-            (DocumentContent::Plan(p), DocumentContent::Body(b)) => (p, b),
-            (DocumentContent::Body(b), DocumentContent::Plan(p)) => (p, b),
-            _ => unreachable!(),
-        };
-
-        Ok(Self {
-            preamble,
-            plan,
-            body,
-        })
+            .ok_or(TapError::Empty)??;
+        if let Self::Subtest(subtest) = &mut statement {
+            strip_comments(&mut subtest.body);
+        }
+        Ok(statement)
     }
 
-    /// Parse [`Document`] from a `&str`.
+    /// The [`Test`], if this is a [`Statement::Test`].
     ///
     /// # Examples
     ///
-    /// Parsing a TAP document may look like this:
-    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!("TAP version 14\n", "1..2\n", "ok 1\n", "Bail out! boom\n", "not ok 2\n");
+    /// let doc = Document::parse_from_str(content).expect("Parser error");
+    /// let results: Vec<_> = doc.body.iter().filter_map(|s| s.as_test()).map(|t| t.result).collect();
+    /// assert_eq!(results, [true, false]);
+    /// assert_eq!(doc.body[1].as_bail_out().and_then(|b| b.reason), Some("boom"));
+    /// assert!(doc.body[1].as_test().is_none());
+    /// ```
+    pub fn as_test(&self) -> Option<&Test<'a>> {
+        match self {
+            Self::Test(test) => Some(test),
+            _ => None,
+        }
+    }
+
+    /// The [`Subtest`], if this is a [`Statement::Subtest`].
+    pub fn as_subtest(&self) -> Option<&Subtest<'a>> {
+        match self {
+            Self::Subtest(subtest) => Some(subtest),
+            _ => None,
+        }
+    }
+
+    /// The [`BailOut`], if this is a [`Statement::BailOut`].
+    pub fn as_bail_out(&self) -> Option<&BailOut<'a>> {
+        match self {
+            Self::BailOut(bail_out) => Some(bail_out),
+            _ => None,
+        }
+    }
+
+    /// The [`Pragma`], if this is a [`Statement::Pragma`].
+    pub fn as_pragma(&self) -> Option<&Pragma<'a>> {
+        match self {
+            Self::Pragma(pragma) => Some(pragma),
+            _ => None,
+        }
+    }
+
+    /// The text, if this is a [`Statement::Anything`].
+    pub fn as_anything(&self) -> Option<&'a str> {
+        match self {
+            Self::Anything(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// The comment text, if this is a [`Statement::Comment`].
+    pub fn as_comment(&self) -> Option<&'a str> {
+        match self {
+            Self::Comment(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Body<'a> {
+    /// Parse [`Body`] from a `&str`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::{Body, Statement};
+    ///
+    /// let content = concat!("ok 1 - foo()\n", "not ok 2 - bar()\n");
+    /// let body = Body::parse_from_str(content).expect("Parser error");
+    /// assert_eq!(body.statements().len(), 2);
+    /// for statement in &body {
+    ///     assert!(matches!(statement, Statement::Test(_)));
+    /// }
+    /// ```
+    pub fn parse_from_str(content: &'a str) -> Result<Self> {
+        check_depth(content, ParseOptions::DEFAULT_MAX_DEPTH)?;
+        let pair = TAPParser::parse(Rule::body, content)?
+            .next()
+            .ok_or(TapError::Empty)?;
+        let span = Span::from_pair(&pair);
+        let mut statements = pair
+            .into_inner()
+            .map(Statement::parse)
+            .collect::<Result<Vec<_>>>()?;
+        strip_comments(&mut statements);
+        number_tests(&mut statements);
+        Ok(Self { statements, span })
+    }
+
+    /// The [`Statement`]s of this [`Body`].
+    pub fn statements(&self) -> &[Statement<'a>] {
+        &self.statements
+    }
+
+    /// Location of the [`Body`] in the source. Slicing the source by its byte offsets reproduces the body verbatim,
+    /// including any comments dropped while parsing. A [`Body`] created from a [`Vec`] has a [`Default`] span.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Body;
+    ///
+    /// let content = "ok 1\n# not a statement\nok 2\n";
+    /// let body = Body::parse_from_str(content).expect("Parser error");
+    /// let span = body.span();
+    /// assert_eq!(body.statements().len(), 2);
+    /// assert_eq!(&content[span.start..span.end], content);
+    /// ```
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl<'a> From<Vec<Statement<'a>>> for Body<'a> {
+    fn from(statements: Vec<Statement<'a>>) -> Self {
+        Self {
+            statements,
+            span: Span::default(),
+        }
+    }
+}
+
+impl<'a> IntoIterator for Body<'a> {
+    type Item = Statement<'a>;
+    type IntoIter = alloc::vec::IntoIter<Statement<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.statements.into_iter()
+    }
+}
+
+impl<'b, 'a> IntoIterator for &'b Body<'a> {
+    type Item = &'b Statement<'a>;
+    type IntoIter = core::slice::Iter<'b, Statement<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.statements.iter()
+    }
+}
+
+impl<'a> DocumentContent<'a> {
+    fn parse(pair: Pair<'a, Rule>) -> Result<Self> {
+        Ok(match pair.as_rule() {
+            Rule::plan => Self::Plan(Plan::parse(pair.into_inner())?),
+            Rule::body => {
+                let mut statements = pair
+                    .into_inner()
+                    .map(Statement::parse)
+                    .collect::<Result<Vec<_>>>()?;
+                number_tests(&mut statements);
+                Self::Body(statements)
+            }
+            _ => unreachable!(),
+        })
+    }
+}
+
+impl<'a> Document<'a> {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            target = "tap",
+            level = "trace",
+            name = "document",
+            skip_all,
+            fields(line = pairs.peek().map(|p| p.line_col().0)),
+            err(level = "debug", Display)
+        )
+    )]
+    pub fn parse(mut pairs: Pairs<'a, Rule>) -> Result<Self> {
+        let span = match (pairs.peek(), pairs.clone().last()) {
+            (Some(first), Some(last)) => pest::Span::new(
+                first.get_input(),
+                first.as_span().start(),
+                last.as_span().end(),
+            )
+            .map_or_else(Span::default, Span::from_pest),
+            _ => Span::default(),
+        };
+        let preamble = match pairs.peek().map(|p| p.as_rule()) {
+            Some(Rule::preamble) => Preamble::parse(pairs.next().unwrap().into_inner()),
+            _ => Preamble { version: "13" },
+        };
+
+        let content1 = DocumentContent::parse(pairs.next().unwrap())?;
+        let content2 = DocumentContent::parse(pairs.next().unwrap())?;
+        let (plan, body) = match (content1, content2) {
+            (DocumentContent::Plan(p), DocumentContent::Body(b)) => (p, b),
+            (DocumentContent::Body(b), DocumentContent::Plan(p)) => (p, b),
+            _ => unreachable!(),
+        };
+
+        Ok(Self {
+            preamble,
+            plan,
+            body,
+            span,
+        })
+    }
+
+    /// Parse [`Document`] from a `&str`.
+    ///
+    /// # Examples
+    ///
+    /// Parsing a TAP document may look like this:
+    ///
     /// ```
     /// use tapconsooomer::Document;
     ///
@@ -693,6 +1563,18 @@ impl<'a> Document<'a> {
     /// assert_eq!(doc.body.len(), 1);
     /// ```
     ///
+    /// Alternatively, use [`TryFrom`], which is implemented by every type offering a `parse_from_str`:
+    ///
+    /// ```
+    /// use std::convert::TryInto;
+    /// use tapconsooomer::{Document, TapError};
+    ///
+    /// let content = "TAP version 14\n1..1\nok 1 - foo()\n";
+    /// let doc: Document = content.try_into()?;
+    /// assert_eq!(doc.body.len(), 1);
+    /// # Ok::<(), TapError>(())
+    /// ```
+    ///
     /// The order in which [`Body`] and [`Plan`] are declared is unimportant:
     ///
     /// ```
@@ -709,19 +1591,796 @@ impl<'a> Document<'a> {
     /// assert_eq!(doc.body.len(), 2);
     /// ```
     pub fn parse_from_str(content: &'a str) -> Result<Self> {
-        TAPParser::parse(Rule::document, content)?
+        Self::parse_from_str_with_options(content, &ParseOptions::default())
+    }
+
+    /// Parse [`Document`] from a `&str`, applying the given [`ParseOptions`].
+    ///
+    /// # Examples
+    ///
+    /// With [`ParseOptions::comment_metadata`] enabled, `# @key: value` comments are attached to the following test:
+    ///
+    /// ```
+    /// use tapconsooomer::{Document, ParseOptions, Statement};
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..1\n",
+    ///     "# @owner: alice\n",
+    ///     "# @tags: network, slow\n",
+    ///     "ok 1 - foo()\n",
+    /// );
+    /// let options = ParseOptions::new().comment_metadata(true);
+    /// let doc = Document::parse_from_str_with_options(content, &options).expect("Parser error");
+    /// let Statement::Test(test) = &doc.body[0] else { panic!() };
+    /// assert_eq!(test.metadata["owner"], "alice");
+    /// assert_eq!(test.metadata["tags"], "network, slow");
+    /// ```
+    ///
+    /// With [`ParseOptions::stop_at_bail_out`] enabled, anything following a [`BailOut`] is kept as raw text:
+    ///
+    /// ```
+    /// use tapconsooomer::{Document, ParseOptions, Statement};
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..3\n",
+    ///     "ok 1 - foo()\n",
+    ///     "Bail out! Database unreachable\n",
+    ///     "ok 2 - bar()\n",
+    ///     "ok 3 - baz()\n",
+    /// );
+    /// let options = ParseOptions::new().stop_at_bail_out(true);
+    /// let doc = Document::parse_from_str_with_options(content, &options).expect("Parser error");
+    /// assert_eq!(doc.body.len(), 3);
+    /// assert!(matches!(doc.body[1], Statement::BailOut(_)));
+    /// assert!(matches!(doc.body[2], Statement::Anything("ok 2 - bar()\nok 3 - baz()")));
+    /// ```
+    ///
+    /// With [`ParseOptions::comments`] enabled, standalone comment lines are preserved as [`Statement::Comment`]:
+    ///
+    /// ```
+    /// use tapconsooomer::{Document, ParseOptions, Statement};
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..1\n",
+    ///     "# Connecting to localhost\n",
+    ///     "ok 1 - foo()\n",
+    /// );
+    /// let options = ParseOptions::new().comments(true);
+    /// let doc = Document::parse_from_str_with_options(content, &options).expect("Parser error");
+    /// assert!(matches!(doc.body[0], Statement::Comment("Connecting to localhost")));
+    /// assert_eq!(Document::parse_from_str(content).expect("Parser error").body.len(), 1);
+    /// ```
+    ///
+    /// Empty or blank content, e.g. of a test command producing no output at all, fails with [`TapError::Empty`]. With
+    /// [`ParseOptions::implicit_preamble`] enabled, a document consisting of nothing but the preamble is accepted as an
+    /// empty run, planning no tests with `1..0`:
+    ///
+    /// ```
+    /// use tapconsooomer::{Document, ParseOptions, TapError};
+    ///
+    /// assert!(matches!(Document::parse_from_str("\n\n"), Err(TapError::Empty)));
+    ///
+    /// let content = "TAP version 14\n";
+    /// assert!(Document::parse_from_str(content).is_err());
+    /// let doc = Document::parse_lenient(content).expect("Parser error");
+    /// assert_eq!((doc.plan.first, doc.plan.last), (1, 0));
+    /// assert!(doc.body.is_empty());
+    /// ```
+    pub fn parse_from_str_with_options(content: &'a str, options: &ParseOptions) -> Result<Self> {
+        Self::parse_until(content, options).map(|(document, _)| document)
+    }
+
+    /// Parse [`Document`] from a `&str`, failing with [`TapError::TrailingInput`] if anything but whitespace follows
+    /// the end of the document.
+    ///
+    /// The document ends after its plan and body, in whichever order they come. [`Document::parse_from_str`] silently
+    /// ignores anything following, e.g. a non-TAP epilogue after a trailing plan or the remains of a botched
+    /// concatenation. This reports it instead, along with where it starts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::{Document, TapError};
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "ok 1 - foo()\n",
+    ///     "1..1\n",
+    ///     "Done in 0.3s\n",
+    /// );
+    /// assert_eq!(Document::parse_from_str(content).expect("Parser error").body.len(), 1);
+    ///
+    /// let err = Document::parse_exact(content).unwrap_err();
+    /// assert!(matches!(err, TapError::TrailingInput { offset: 33, line: 4 }));
+    ///
+    /// assert!(Document::parse_exact("TAP version 14\n1..1\nok 1\n\n  \n").is_ok());
+    /// ```
+    pub fn parse_exact(content: &'a str) -> Result<Self> {
+        let (document, end) = Self::parse_until(content, &ParseOptions::default())?;
+        match content[end..].find(|c: char| !c.is_whitespace()) {
+            Some(offset) => {
+                let offset = end + offset;
+                Err(TapError::TrailingInput {
+                    offset,
+                    line: Position::new(content, offset).unwrap().line_col().0,
+                })
+            }
+            None => Ok(document),
+        }
+    }
+
+    /// Parse [`Document`] like [`Document::parse_from_str_with_options`], also returning the byte offset at which
+    /// the document ends.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            target = "tap",
+            level = "debug",
+            skip_all,
+            name = "parse_document",
+            fields(bytes = content.len()),
+            err(level = "debug", Display)
+        )
+    )]
+    pub(crate) fn parse_until(content: &'a str, options: &ParseOptions) -> Result<(Self, usize)> {
+        if content.trim().is_empty() {
+            return Err(TapError::Empty);
+        }
+        if let Some(max) = options.max_bytes.filter(|&max| content.len() > max) {
+            return Err(TapError::LimitExceeded {
+                limit: Limit::Bytes,
+                max,
+            });
+        }
+        if options.implicit_preamble {
+            if let Some(preamble) = preamble_only(content) {
+                let document = Self {
+                    preamble,
+                    plan: Plan::default(),
+                    body: Vec::new(),
+                    span: Span::from_pest(pest::Span::new(content, 0, content.len()).unwrap()),
+                };
+                return Ok((document, content.len()));
+            }
+        }
+        let rule = if options.implicit_preamble {
+            Rule::lenient_document
+        } else {
+            Rule::document
+        };
+        check_depth(content, options.max_depth)?;
+        let pair = TAPParser::parse(rule, content)?
             .next()
-            .map(Pair::into_inner)
-            .map(Self::parse)
-            .ok_or_else(|| anyhow!("Can't parse '{}'", content))?
+            .ok_or(TapError::Empty)?;
+        let end = pair.as_span().end();
+        let body_end = pair
+            .clone()
+            .into_inner()
+            .find(|p| p.as_rule() == Rule::body)
+            .map_or(content.len(), |p| p.as_span().end());
+        let trailing_plan = pair
+            .clone()
+            .into_inner()
+            .find(|p| p.as_rule() == Rule::plan)
+            .filter(|p| p.as_span().start() >= body_end)
+            .map(|p| p.line_col().0);
+        let mut document = Self::parse(pair.into_inner())?;
+        let tail = if options.stop_at_bail_out {
+            truncate_after_bail_out(&mut document.body, &content[..body_end])
+        } else {
+            None
+        };
+        if !options.comments {
+            strip_comments(&mut document.body);
+        }
+        if options.comment_metadata {
+            attach_metadata(&mut document.body, content);
+        }
+        if options.pragmas {
+            check_strict(&document.body)?;
+        }
+        if options.strict_plan_position {
+            let line = Position::new(content, end).unwrap().line_col().0;
+            check_plan_position(&document.body, &content[end..], line, trailing_plan)?;
+        }
+        document.body.extend(tail.map(Statement::Anything));
+        if let Some(max) = options
+            .max_statements
+            .filter(|&max| count_statements(&document.body) > max)
+        {
+            return Err(TapError::LimitExceeded {
+                limit: Limit::Statements,
+                max,
+            });
+        }
+        Ok((document, end))
+    }
+
+    /// Parse [`Document`] from a `&str`, honoring the `strict` pragma. Shorthand for
+    /// [`Document::parse_from_str_with_options`] with [`ParseOptions::pragmas`] enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::{Document, TapError};
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..2\n",
+    ///     "ok 1 - foo()\n",
+    ///     "pragma +strict\n",
+    ///     "garbage\n",
+    ///     "ok 2 - bar()\n",
+    /// );
+    /// assert!(Document::parse_from_str(content).is_ok());
+    ///
+    /// let err = Document::parse_from_str_with_pragmas(content).unwrap_err();
+    /// assert!(matches!(err, TapError::NonTapLine(line) if line == "garbage"));
+    /// ```
+    pub fn parse_from_str_with_pragmas(content: &'a str) -> Result<Self> {
+        Self::parse_from_str_with_options(content, &ParseOptions::new().pragmas(true))
+    }
+
+    /// Parse [`Document`] from a `&str`, tolerating a missing `TAP version` preamble. Shorthand for
+    /// [`Document::parse_from_str_with_options`] with [`ParseOptions::implicit_preamble`] enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!(
+    ///     "1..2\n",
+    ///     "ok 1 - foo()\n",
+    ///     "ok 2 - bar()\n",
+    /// );
+    /// assert!(Document::parse_from_str(content).is_err());
+    ///
+    /// let doc = Document::parse_lenient(content).expect("Parser error");
+    /// assert_eq!(doc.preamble.version, "13");
+    /// assert_eq!(doc.body.len(), 2);
+    /// ```
+    pub fn parse_lenient(content: &'a str) -> Result<Self> {
+        Self::parse_from_str_with_options(content, &ParseOptions::new().implicit_preamble(true))
+    }
+
+    /// Parse [`Document`] from raw bytes, which may contain invalid UTF-8, e.g. binary dumps in test descriptions.
+    ///
+    /// Invalid sequences are replaced by `U+FFFD REPLACEMENT CHARACTER` ahead of parsing, so a stray byte doesn't make
+    /// the whole output unparseable. As the converted text doesn't outlive this call, an [`OwnedDocument`] is
+    /// returned. Spans and error positions refer to the converted text, which is longer than `bytes` wherever a
+    /// sequence was replaced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = b"TAP version 14\n1..1\nok 1 - dump \xff\xfe\n";
+    /// let doc = Document::parse_from_bytes(content).expect("Parser error");
+    /// let test = doc.as_borrowed().tests().next().cloned().unwrap();
+    /// assert_eq!(test.description.as_deref(), Some("dump \u{fffd}\u{fffd}"));
+    /// ```
+    pub fn parse_from_bytes(bytes: &[u8]) -> Result<OwnedDocument> {
+        let content = String::from_utf8_lossy(bytes);
+        Document::parse_from_str(&content).map(Document::into_owned)
+    }
+
+    /// Parse several [`Document`]s following each other, e.g. the aggregated output of several test runs.
+    ///
+    /// Each line starting with `TAP version` begins a new document, which ends where the next one begins. Any content
+    /// ahead of the first preamble is ignored, so content without any preamble yields no documents at all. Spans refer
+    /// to the whole `content`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..1\n",
+    ///     "ok 1 - first run\n",
+    ///     "TAP version 14\n",
+    ///     "1..2\n",
+    ///     "ok 1 - second run\n",
+    ///     "not ok 2 - second run\n",
+    /// );
+    /// let docs = Document::parse_many(content).expect("Parser error");
+    /// assert_eq!(docs.len(), 2);
+    /// assert_eq!(docs[1].plan.last, 2);
+    /// assert_eq!(docs[1].tests().next().unwrap().span.start_line, 6);
+    /// ```
+    pub fn parse_many(content: &'a str) -> Result<Vec<Self>> {
+        let mut starts = Vec::new();
+        let mut offset = 0;
+        for (index, line) in content.split_inclusive('\n').enumerate() {
+            if line.len() >= 11
+                && line.is_char_boundary(11)
+                && line[..11].eq_ignore_ascii_case("tap version")
+            {
+                starts.push((offset, index));
+            }
+            offset += line.len();
+        }
+
+        let ends = starts.iter().skip(1).map(|&(offset, _)| offset);
+        starts
+            .iter()
+            .zip(ends.chain(core::iter::once(content.len())))
+            .map(|(&(start, lines), end)| {
+                let mut document = Self::parse_from_str(&content[start..end])?;
+                for statement in &mut document.body {
+                    statement.shift_spans(start, lines);
+                }
+                Ok(document)
+            })
+            .collect()
+    }
+
+    /// Collect all [`Test`]s, including those nested inside [`Subtest`]s, that appear after the first top-level
+    /// [`BailOut`]. Per TAP semantics a harness _should_ stop after bailing out, so any such tests are suspect.
+    ///
+    /// Returns an empty [`Vec`] if the document contains no top-level [`BailOut`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..3\n",
+    ///     "ok 1 - foo()\n",
+    ///     "Bail out! Database unreachable\n",
+    ///     "ok 2 - bar()\n",
+    ///     "not ok 3 - baz()\n",
+    /// );
+    /// let doc = Document::parse_from_str(content).expect("Parser error");
+    /// let suspects = doc.tests_after_bail();
+    /// assert_eq!(suspects.len(), 2);
+    /// assert_eq!(suspects[0].description.as_deref(), Some("bar()"));
+    /// assert_eq!(suspects[1].description.as_deref(), Some("baz()"));
+    /// ```
+    pub fn tests_after_bail(&self) -> Vec<&Test<'a>> {
+        fn collect<'b, 'a>(statements: &'b [Statement<'a>], tests: &mut Vec<&'b Test<'a>>) {
+            for statement in statements {
+                match statement {
+                    Statement::Test(t) => tests.push(t),
+                    Statement::Subtest(s) => collect(&s.body, tests),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut tests = Vec::new();
+        if let Some(i) = self
+            .body
+            .iter()
+            .position(|s| matches!(s, Statement::BailOut(_)))
+        {
+            collect(&self.body[i + 1..], &mut tests);
+        }
+        tests
+    }
+
+    /// Collect all `ok` [`Test`]s with a [`Key::Todo`] directive, including those nested inside [`Subtest`]s. Such a
+    /// "TODO passed" signals that the TODO can be removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..3\n",
+    ///     "ok 1 - foo() # TODO fixed by now\n",
+    ///     "not ok 2 - bar() # TODO not implemented\n",
+    ///     "ok 3 - baz()\n",
+    /// );
+    /// let doc = Document::parse_from_str(content).expect("Parser error");
+    /// let passes = doc.unexpected_passes();
+    /// assert_eq!(passes.len(), 1);
+    /// assert_eq!(passes[0].number, Some(1));
+    /// ```
+    pub fn unexpected_passes(&self) -> Vec<&Test<'a>> {
+        self.todo_tests(true)
+    }
+
+    /// Collect all `not ok` [`Test`]s with a [`Key::Todo`] directive, including those nested inside [`Subtest`]s.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..3\n",
+    ///     "ok 1 - foo() # TODO fixed by now\n",
+    ///     "not ok 2 - bar() # TODO not implemented\n",
+    ///     "not ok 3 - baz()\n",
+    /// );
+    /// let doc = Document::parse_from_str(content).expect("Parser error");
+    /// let failures = doc.expected_failures();
+    /// assert_eq!(failures.len(), 1);
+    /// assert_eq!(failures[0].number, Some(2));
+    /// ```
+    pub fn expected_failures(&self) -> Vec<&Test<'a>> {
+        self.todo_tests(false)
+    }
+
+    /// Group all [`Test`]s with a [`Key::Skip`] directive by its reason, including those nested inside [`Subtest`]s.
+    /// Tests without a reason are grouped under [`None`]. Each group keeps the tests in document order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..4\n",
+    ///     "ok 1 - foo() # SKIP no network\n",
+    ///     "ok 2 - bar() # SKIP\n",
+    ///     "# Subtest: baz\n",
+    ///     "    1..1\n",
+    ///     "    ok 1 - qux() # SKIP no network\n",
+    ///     "ok 3 - baz()\n",
+    ///     "ok 4 - quux() # TODO later\n",
+    /// );
+    /// let doc = Document::parse_from_str(content).expect("Parser error");
+    /// let reasons = doc.skip_reasons();
+    /// assert_eq!(reasons.len(), 2);
+    /// let lines: Vec<_> = reasons[&Some("no network")].iter().map(|t| t.span.start_line).collect();
+    /// assert_eq!(lines, [3, 7]);
+    /// assert_eq!(reasons[&None][0].number, Some(2));
+    /// ```
+    pub fn skip_reasons(&self) -> BTreeMap<Option<&'a str>, Vec<&Test<'a>>> {
+        self.directive_reasons(Key::Skip)
+    }
+
+    /// Group all [`Test`]s with a [`Key::Todo`] directive by its reason, like [`Document::skip_reasons`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..3\n",
+    ///     "not ok 1 - foo() # TODO flaky\n",
+    ///     "ok 2 - bar() # TODO flaky\n",
+    ///     "not ok 3 - baz() # SKIP flaky\n",
+    /// );
+    /// let doc = Document::parse_from_str(content).expect("Parser error");
+    /// let reasons = doc.todo_reasons();
+    /// assert_eq!(reasons.len(), 1);
+    /// assert_eq!(reasons[&Some("flaky")].len(), 2);
+    /// ```
+    pub fn todo_reasons(&self) -> BTreeMap<Option<&'a str>, Vec<&Test<'a>>> {
+        self.directive_reasons(Key::Todo)
+    }
+
+    fn directive_reasons(&self, key: Key<'_>) -> BTreeMap<Option<&'a str>, Vec<&Test<'a>>> {
+        let mut reasons: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for test in self.tests() {
+            if let Some(directive) = test.directive.as_ref().filter(|d| d.key == key) {
+                reasons.entry(directive.reason).or_default().push(test);
+            }
+        }
+        reasons
+    }
+
+    fn todo_tests(&self, result: bool) -> Vec<&Test<'a>> {
+        self.tests()
+            .filter(|t| t.result == result && matches!(&t.directive, Some(d) if d.key == Key::Todo))
+            .collect()
+    }
+
+    /// Prune the body down to the `not ok` [`Test`]s, including TODO failures, and the [`Subtest`]s containing them,
+    /// preserving the nesting. [`BailOut`]s are kept, as they explain why tests are missing. Anything else is removed.
+    ///
+    /// The [`Plan`]s are left untouched, so they still describe the whole run and test numbers keep referring to them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::{Document, Statement};
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..3\n",
+    ///     "ok 1 - foo()\n",
+    ///     "# Subtest: bar\n",
+    ///     "    1..2\n",
+    ///     "    ok 1 - fine\n",
+    ///     "    not ok 2 - broken\n",
+    ///     "not ok 2 - bar\n",
+    ///     "# Subtest: baz\n",
+    ///     "    1..1\n",
+    ///     "    ok 1 - fine\n",
+    ///     "ok 3 - baz\n",
+    /// );
+    /// let mut doc = Document::parse_from_str(content).expect("Parser error");
+    /// doc.retain_failures();
+    /// assert_eq!(doc.body.len(), 2);
+    /// let Statement::Subtest(subtest) = &doc.body[0] else { panic!() };
+    /// assert_eq!(subtest.body.len(), 1);
+    /// let failures: Vec<_> = doc.tests().map(|t| t.description.as_deref()).collect();
+    /// assert_eq!(failures, [Some("broken"), Some("bar")]);
+    /// assert_eq!(doc.plan.last, 3);
+    /// ```
+    pub fn retain_failures(&mut self) {
+        fn retain(statements: &mut Vec<Statement>) {
+            statements.retain_mut(|statement| match statement {
+                Statement::Test(test) => !test.result,
+                Statement::Subtest(subtest) => {
+                    retain(&mut subtest.body);
+                    !subtest.body.is_empty()
+                }
+                Statement::BailOut(_) => true,
+                _ => false,
+            });
+        }
+
+        retain(&mut self.body);
+    }
+}
+
+/// Implement [`TryFrom<&str>`] for each of the given types by delegating to their `parse_from_str`.
+macro_rules! impl_try_from_str {
+    ($($ty:ident),*) => {
+        $(
+            impl<'a> TryFrom<&'a str> for $ty<'a> {
+                type Error = TapError;
+
+                #[doc = concat!("Shorthand for [`", stringify!($ty), "::parse_from_str`].")]
+                fn try_from(content: &'a str) -> Result<Self> {
+                    Self::parse_from_str(content)
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_str!(
+    Preamble, Plan, Directive, Test, BailOut, Pragma, Subtest, Statement, Body, Document
+);
+
+/// The [`Preamble`] of `content`, if it is the only line besides blank and comment lines.
+fn preamble_only(content: &str) -> Option<Preamble<'_>> {
+    let mut lines = content
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim_start().is_empty() && !line.starts_with('#'));
+    match (lines.next(), lines.next()) {
+        (Some(line), None) => Preamble::parse_from_str(line).ok(),
+        _ => None,
+    }
+}
+
+/// Whether `line`, without its line terminator, declares a [`Subtest`], like the grammar's `subtest_decl`: a
+/// case-insensitive `# Subtest`, optionally followed by `:` and a name.
+pub(crate) fn is_subtest_decl(line: &str) -> bool {
+    let Some(marker) = line.trim_end().strip_prefix('#') else {
+        return false;
+    };
+    let marker = marker.trim_start_matches([' ', '\t']);
+    marker
+        .get(..7)
+        .is_some_and(|m| m.eq_ignore_ascii_case("subtest"))
+        && matches!(marker[7..].chars().next(), None | Some(':'))
+}
+
+/// Remove all [`Statement::Comment`]s, including those nested inside [`Subtest`]s.
+fn strip_comments(statements: &mut Vec<Statement>) {
+    statements.retain_mut(|statement| match statement {
+        Statement::Comment(_) => false,
+        Statement::Subtest(subtest) => {
+            strip_comments(&mut subtest.body);
+            true
+        }
+        _ => true,
+    });
+}
+
+/// Assign [`Test::effective_number`] to the tests among `statements`, forming one plan scope. Nested [`Subtest`]s are
+/// numbered when parsed.
+pub(crate) fn number_tests(statements: &mut [Statement]) {
+    let mut number: i32 = 0;
+    for statement in statements {
+        if let Statement::Test(test) = statement {
+            number = test.number.unwrap_or_else(|| number.saturating_add(1));
+            test.effective_number = number;
+        }
+    }
+}
+
+/// Reject `content` nested deeper than `max_depth` levels of indentation, before handing it to the recursive grammar.
+/// Lines are tracked like [`Span::start_line`] does, so a lone `\r` doesn't start a new line.
+fn check_depth(content: &str, max_depth: usize) -> Result<()> {
+    let mut levels: Vec<usize> = Vec::new();
+    for (index, line) in content.split('\n').enumerate() {
+        for line in line.split('\r') {
+            let trimmed = line.trim_start_matches([' ', '\t']);
+            if trimmed.is_empty() {
+                continue;
+            }
+            let indent = line.len() - trimmed.len();
+            while levels.last().is_some_and(|&level| level > indent) {
+                levels.pop();
+            }
+            if indent > levels.last().copied().unwrap_or(0) {
+                levels.push(indent);
+            }
+            if levels.len() > max_depth {
+                return Err(TapError::MaxDepthExceeded {
+                    max_depth,
+                    line: index + 1,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Count `statements`, including those nested inside subtests.
+fn count_statements(statements: &[Statement]) -> usize {
+    statements
+        .iter()
+        .map(|statement| match statement {
+            Statement::Subtest(subtest) => 1 + count_statements(&subtest.body),
+            _ => 1,
+        })
+        .sum()
+}
+
+/// Drop all `statements` following the first [`BailOut`], returning the remainder of `content` after it, if any.
+fn truncate_after_bail_out<'a>(
+    statements: &mut Vec<Statement<'a>>,
+    content: &'a str,
+) -> Option<&'a str> {
+    let index = statements
+        .iter()
+        .position(|s| matches!(s, Statement::BailOut(_)))?;
+    let Statement::BailOut(bail_out) = &statements[index] else {
+        unreachable!()
+    };
+    let tail = content[bail_out.span.end..]
+        .trim_start_matches(['\r', '\n'])
+        .trim_end();
+    statements.truncate(index + 1);
+    (!tail.is_empty()).then_some(tail)
+}
+
+/// Reject [`Statement::Anything`] and [`Key::Other`] while `pragma +strict` is active, see [`strict_violations`].
+fn check_strict(statements: &[Statement]) -> Result<()> {
+    let mut errors = Vec::new();
+    strict_violations(statements, &mut errors);
+    errors.into_iter().next().map_or(Ok(()), Err)
+}
+
+/// Collect an error for each [`Statement::Anything`], [`Key::Other`] and invalid test number encountered while
+/// `pragma +strict` is active. Subtests are checked with a fresh state.
+fn strict_violations(statements: &[Statement], errors: &mut Vec<TapError>) {
+    let mut strict = false;
+    for statement in statements {
+        match statement {
+            Statement::Pragma(p) if p.option == "strict" => strict = p.flag != Some(false),
+            Statement::Anything(a) if strict => errors.push(TapError::NonTapLine(a.to_string())),
+            Statement::Test(Test {
+                directive:
+                    Some(Directive {
+                        key: Key::Other(k), ..
+                    }),
+                ..
+            }) if strict => errors.push(TapError::InvalidDirectiveKey(k.to_string())),
+            Statement::Test(t) if strict && t.has_invalid_number() => {
+                let raw = t.raw_number.unwrap_or_default();
+                if let Err(e) = raw.parse::<i32>() {
+                    errors.push(TapError::NumberParse(e));
+                }
+            }
+            Statement::Subtest(s) => strict_violations(&s.body, errors),
+            _ => {}
+        }
+    }
+}
+
+/// Fail with [`TapError::MisplacedPlan`] if a plan is followed by further tests, see
+/// [`ParseOptions::strict_plan_position`]. The `rest` of the content following the top-level body starts at line
+/// `first_line`, right after the plan at line `trailing_plan` if that follows the body.
+fn check_plan_position(
+    statements: &[Statement],
+    rest: &str,
+    first_line: usize,
+    trailing_plan: Option<usize>,
+) -> Result<()> {
+    check_subtest_plan_position(statements)?;
+    let mut plan = trailing_plan;
+    for (index, line) in rest.lines().enumerate() {
+        if is_indented(line) {
+            continue;
+        }
+        match classify_line(line) {
+            LineKind::Plan => {
+                plan.get_or_insert(first_line + index);
+            }
+            LineKind::Test | LineKind::Subtest | LineKind::BailOut => {
+                return plan.map_or(Ok(()), |line| Err(TapError::MisplacedPlan(line)));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Fail with [`TapError::MisplacedPlan`] if a [`Subtest`] ending at its plan is followed by more of its indented
+/// tests, which the parser leaves behind as [`Statement::Anything`].
+fn check_subtest_plan_position(statements: &[Statement]) -> Result<()> {
+    let mut statements = statements
+        .iter()
+        .filter(|s| !matches!(s, Statement::Comment(_)))
+        .peekable();
+    while let Some(statement) = statements.next() {
+        if let Statement::Subtest(subtest) = statement {
+            check_subtest_plan_position(&subtest.body)?;
+            if let Some(Statement::Anything(line)) = statements.peek() {
+                if is_indented(line)
+                    && matches!(
+                        classify_line(line),
+                        LineKind::Test | LineKind::Subtest | LineKind::BailOut
+                    )
+                {
+                    return Err(TapError::MisplacedPlan(subtest.span.end_line));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parse a `# @key: value` comment line.
+fn parse_metadata(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line
+        .trim()
+        .strip_prefix('#')?
+        .trim_start()
+        .strip_prefix('@')?
+        .split_once(':')?;
+    (!key.is_empty() && !key.contains(char::is_whitespace)).then(|| (key, value.trim()))
+}
+
+/// Attach metadata comments found in `content` directly above each test in `statements`.
+fn attach_metadata<'a>(statements: &mut [Statement<'a>], content: &'a str) {
+    for statement in statements {
+        match statement {
+            Statement::Test(test) => {
+                let preceding = &content[..test.span.start];
+                let preceding = &preceding[..preceding.rfind('\n').unwrap_or(0)];
+                for (key, value) in preceding.lines().rev().map_while(parse_metadata) {
+                    /* Scanning upwards, so the annotation closest to the test wins. */
+                    test.metadata.entry(key).or_insert(value);
+                }
+            }
+            Statement::Subtest(subtest) => attach_metadata(&mut subtest.body, content),
+            _ => {}
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use alloc::{format, string::String, vec};
     use pest::consumes_to;
     use pest::parses_to;
-    use std::fs;
+    use std::{convert::TryInto, fs};
 
     use super::*;
 
@@ -837,6 +2496,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_ok_glued_number() {
+        parses_to! {
+            parser: TAPParser,
+            input : "OK1",
+            rule: Rule::test,
+            tokens: [
+                test(0, 3, [
+                    result(0, 2), number(2, 3)
+                ])
+            ]
+        }
+    }
+
+    #[test]
+    fn test_not_ok_glued_number_with_description() {
+        parses_to! {
+            parser: TAPParser,
+            input : "not ok12 - hello world",
+            rule: Rule::test,
+            tokens: [
+                test(0, 22, [
+                    result(0, 6), number(6, 8), description(11, 22)
+                ])
+            ]
+        }
+    }
+
+    #[test]
+    fn test_ok_with_leading_zeros() {
+        let test = Test::parse_from_str("ok 007 - foo").expect("Parser error");
+        assert_eq!(test.number, Some(7));
+        assert_eq!(test.raw_number, Some("007"));
+        assert_eq!(test.to_string(), "ok 007 - foo");
+    }
+
     #[test]
     fn test_ok_with_description() {
         parses_to! {
@@ -1041,6 +2736,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_subtest_declaration_variants() {
+        for (declaration, name) in [
+            ("# SUBTEST:Foo", Some("Foo")),
+            ("#Subtest", None),
+            ("#  subtest: bar baz", Some("bar baz")),
+            ("# Subtest:", None),
+            ("# Subtest:   ", None),
+            ("# sUbTeSt  ", None),
+        ] {
+            let content = format!("{}\n    1..1\n    ok 1\n", declaration);
+            let subtest = Subtest::parse_from_str(&content).expect("Parser error");
+            assert_eq!(subtest.name, name, "{}", declaration);
+            assert_eq!(subtest.body.len(), 1, "{}", declaration);
+        }
+
+        /* Anything else following the marker makes it a plain comment. */
+        let content = "TAP version 14\n1..1\n# Subtests follow\nok 1\n";
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        assert!(matches!(&doc.body[..], [Statement::Test(_)]));
+    }
+
     #[test]
     fn test_subtest_with_declaration() {
         parses_to! {
@@ -1121,6 +2838,91 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_subtest_multiple_plans() {
+        let content = concat!(
+            "# Subtest: twice\n",
+            "    1..2\n",
+            "    ok 1 - foo\n",
+            "    1..1\n",
+            "    ok 2 - bar\n",
+        );
+        assert!(matches!(
+            Subtest::parse_from_str(content),
+            Err(TapError::MultiplePlans(4))
+        ));
+
+        let document = format!("TAP version 14\n1..1\n{}ok 1 - twice\n", content);
+        assert!(matches!(
+            Document::parse_from_str(&document),
+            Err(TapError::MultiplePlans(6))
+        ));
+    }
+
+    #[test]
+    fn test_strict_plan_position() {
+        let strict = ParseOptions::new().strict_plan_position(true);
+        let cases = [
+            ("TAP version 14\nok 1\n1..2\nok 2\n", 3),
+            ("TAP version 14\n1..1\nok 1\n1..2\n# between\nok 2\n", 4),
+            (
+                "TAP version 14\nok 1\n\n1..3\n\n# Subtest: late\n    1..1\n    ok 1\nok 2\n",
+                4,
+            ),
+            (
+                concat!(
+                    "TAP version 14\n",
+                    "1..1\n",
+                    "# Subtest: inner\n",
+                    "    ok 1\n",
+                    "    1..2\n",
+                    "    ok 2\n",
+                    "ok 1 - inner\n",
+                ),
+                5,
+            ),
+        ];
+        for (content, line) in cases.iter().copied() {
+            assert!(Document::parse_from_str(content).is_ok(), "{}", content);
+            let err = Document::parse_from_str_with_options(content, &strict).unwrap_err();
+            assert!(
+                matches!(err, TapError::MisplacedPlan(l) if l == line),
+                "{}: {:?}",
+                content,
+                err
+            );
+        }
+
+        let valid = [
+            "TAP version 14\n1..2\nok 1\nok 2\n",
+            "TAP version 14\nok 1\nok 2\n1..2\n\n# done\ngarbage\n",
+            "TAP version 14\n1..1\n# Subtest: inner\n    ok 1\n    1..1\nok 1 - inner\n",
+        ];
+        for content in valid.iter().copied() {
+            assert!(
+                Document::parse_from_str_with_options(content, &strict).is_ok(),
+                "{}",
+                content
+            );
+        }
+    }
+
+    #[test]
+    fn test_subtest_missing_plan() {
+        let content = "# Subtest: planless\n    ok 1 - foo\nok 1 - planless\n";
+        assert!(Subtest::parse_from_str(content).is_err());
+
+        /* The grammar never yields a subtest without plan, so feed some other pair instead. */
+        let pair = TAPParser::parse(Rule::body, "ok 1 - foo\n")
+            .expect("Parser error")
+            .next()
+            .unwrap();
+        assert!(matches!(
+            Subtest::parse(pair),
+            Err(TapError::MissingPlan(1))
+        ));
+    }
+
     #[test]
     fn test_comment() {
         parses_to! {
@@ -1132,6 +2934,352 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_escaped_hash_description() {
+        let test =
+            Test::parse_from_str(r"ok 1 - compute a \# b # SKIP not today").expect("Parser error");
+        assert_eq!(test.description.as_deref(), Some("compute a # b"));
+        assert!(matches!(
+            test.directive,
+            Some(Directive { key: Key::Skip, .. })
+        ));
+
+        let test = Test::parse_from_str("ok 2 - plain").expect("Parser error");
+        assert!(matches!(test.description, Some(Cow::Borrowed("plain"))));
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let contents = fs::read_to_string("examples/yaml.tap").expect("Failed to read file");
+        let lf = Document::parse_from_str(&contents).expect("Parser error");
+        let key = |doc: &Document| {
+            let tests: Vec<_> = doc
+                .tests()
+                .map(|t| OwnedTest {
+                    yaml_raw: None,
+                    span: Span::default(),
+                    ..t.into()
+                })
+                .collect();
+            (doc.plan.reason.map(str::to_owned), tests)
+        };
+
+        for newline in ["\r\n", "\r"] {
+            let converted = contents.replace('\n', newline);
+            let doc = Document::parse_from_str(&converted).expect("Parser error");
+            assert_eq!(key(&doc), key(&lf));
+            for test in doc.tests() {
+                assert!(!converted[test.span.start..test.span.end].ends_with('\r'));
+            }
+        }
+
+        let doc = Document::parse_from_str(concat!(
+            "TAP version 14\r\n",
+            "1..2 # plan reason\r\n",
+            "ok 1 - foo\r\n",
+            "not ok 2 - bar # TODO later\r\n",
+            "Bail out! stop\r\n",
+        ))
+        .expect("Parser error");
+        assert_eq!(doc.plan.reason, Some("plan reason"));
+        let tests: Vec<_> = doc.tests().collect();
+        assert_eq!(tests[0].description.as_deref(), Some("foo"));
+        assert_eq!(
+            tests[1].directive.as_ref().and_then(|d| d.reason),
+            Some("later")
+        );
+        assert!(matches!(
+            doc.body.last(),
+            Some(Statement::BailOut(BailOut {
+                reason: Some("stop"),
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        let contents = fs::read_to_string("examples/cascading.tap").expect("Failed to read file");
+        let doc = Document::parse_lenient(&contents).expect("Parser error");
+        assert_eq!(
+            doc,
+            Document::parse_from_str(&contents).expect("Parser error")
+        );
+
+        let (_, headless) = contents.split_once('\n').unwrap();
+        assert!(Document::parse_from_str(headless).is_err());
+        let lenient = Document::parse_lenient(headless).expect("Parser error");
+        assert_eq!(lenient.preamble.version, "13");
+        assert_eq!(lenient.plan, doc.plan);
+        assert_eq!(lenient.tests().count(), doc.tests().count());
+
+        let lenient = Document::parse_lenient("ok 1 - foo\n1..1\n").expect("Parser error");
+        assert_eq!(lenient.preamble.version, "13");
+        assert_eq!(lenient.body.len(), 1);
+    }
+
+    #[test]
+    fn test_empty_input() {
+        for content in ["", "\n", "  \n\t\r\n"] {
+            assert!(matches!(
+                Document::parse_from_str(content),
+                Err(TapError::Empty)
+            ));
+            assert!(matches!(
+                Document::parse_lenient(content),
+                Err(TapError::Empty)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_parse_exact_concatenated() {
+        let run = "TAP version 14\nok 1\n1..1\n";
+        assert!(Document::parse_exact(run).is_ok());
+
+        let content = format!("{}{}", run, run);
+        assert!(Document::parse_from_str(&content).is_ok());
+        assert!(matches!(
+            Document::parse_exact(&content),
+            Err(TapError::TrailingInput {
+                offset: 25,
+                line: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn test_preamble_only() {
+        let content = "\nTAP version 14\n# no tests\n\n";
+        assert!(Document::parse_from_str(content).is_err());
+
+        let doc = Document::parse_lenient(content).expect("Parser error");
+        assert_eq!(doc.preamble.version, "14");
+        assert!(doc.plan.is_skip_all());
+        assert!(doc.body.is_empty());
+        assert_eq!(doc.summary().total, 0);
+
+        assert!(Document::parse_lenient("TAP version 14\nok 1\n").is_err());
+    }
+
+    #[test]
+    fn test_other_directive_key() {
+        let test = Test::parse_from_str("not ok 1 - foo # XFAIL known bug").expect("Parser error");
+        assert_eq!(test.description.as_deref(), Some("foo"));
+        assert_eq!(
+            test.directive,
+            Some(Directive {
+                key: Key::Other("XFAIL"),
+                reason: Some("known bug"),
+                raw_reason: Some(" known bug"),
+            })
+        );
+        assert_eq!(test.to_string(), "not ok 1 - foo # XFAIL known bug");
+
+        let test = Test::parse_from_str("ok 2 - bar # just a comment").expect("Parser error");
+        assert_eq!(test.directive, None);
+
+        /* Uppercase words other than XFAIL and XPASS are comments, not directives. */
+        for content in ["ok 3 - baz # NOTE flaky", "ok 3 - baz # XFAILED"] {
+            let test = Test::parse_from_str(content).expect("Parser error");
+            assert_eq!(test.description.as_deref(), Some("baz"));
+            assert_eq!(test.directive, None);
+        }
+        let content = concat!(
+            "TAP version 14\n",
+            "1..1\n",
+            "pragma +strict\n",
+            "ok 1 - foo # NOTE flaky\n"
+        );
+        assert!(Document::parse_from_str_with_pragmas(content).is_ok());
+
+        let content = concat!(
+            "TAP version 14\n",
+            "1..2\n",
+            "ok 1 # XFAIL\n",
+            "pragma +strict\n",
+            "ok 2 # XFAIL\n",
+        );
+        assert!(Document::parse_from_str(content).is_ok());
+        assert!(matches!(
+            Document::parse_from_str_with_pragmas(content),
+            Err(TapError::InvalidDirectiveKey(key)) if key == "XFAIL"
+        ));
+    }
+
+    #[test]
+    fn test_directive_reason_trimming() {
+        for (content, reason, raw_reason) in [
+            (
+                "ok 1 # SKIP not supported",
+                Some("not supported"),
+                Some(" not supported"),
+            ),
+            (
+                "ok 1 # SKIP: not supported",
+                Some("not supported"),
+                Some(": not supported"),
+            ),
+            (
+                "ok 1 # skip : not supported  ",
+                Some("not supported"),
+                Some(" : not supported  "),
+            ),
+            ("ok 1 # TODO::twice", Some(":twice"), Some("::twice")),
+            ("ok 1 # TODO:", None, Some(":")),
+            ("ok 1 # TODO", None, None),
+            (
+                "ok 1 # XFAIL: known bug",
+                Some("known bug"),
+                Some(": known bug"),
+            ),
+        ] {
+            let test = Test::parse_from_str(content).expect("Parser error");
+            let directive = test.directive.expect("Missing directive");
+            assert_eq!(directive.reason, reason, "{}", content);
+            assert_eq!(directive.raw_reason, raw_reason, "{}", content);
+        }
+    }
+
+    #[test]
+    fn test_stop_at_bail_out() {
+        let content = concat!(
+            "TAP version 14\n",
+            "ok 1 - foo\n",
+            "Bail out! boom\n",
+            "\n",
+            "garbage\n",
+            "# Subtest: sub\n",
+            "    1..1\n",
+            "    ok 1\n",
+            "ok 2 - sub\n",
+            "1..2\n",
+        );
+        let options = ParseOptions::new().stop_at_bail_out(true).pragmas(true);
+        let doc = Document::parse_from_str_with_options(content, &options).expect("Parser error");
+        assert_eq!((doc.plan.first, doc.plan.last), (1, 2));
+        assert_eq!(doc.tests().count(), 1);
+        assert!(matches!(
+            doc.body.last(),
+            Some(Statement::Anything(tail))
+                if *tail == "garbage\n# Subtest: sub\n    1..1\n    ok 1\nok 2 - sub"
+        ));
+        assert_eq!(
+            Document::parse_from_str(content)
+                .expect("Parser error")
+                .tests()
+                .count(),
+            3
+        );
+
+        let doc =
+            Document::parse_from_str_with_options("TAP version 14\n1..1\nBail out!\n", &options)
+                .expect("Parser error");
+        assert!(matches!(doc.body[..], [Statement::BailOut(_)]));
+    }
+
+    #[test]
+    fn test_try_from_str() {
+        let plan = Plan::try_from("1..3 # reason").expect("Parser error");
+        assert_eq!(
+            plan,
+            Plan::parse_from_str("1..3 # reason").expect("Parser error")
+        );
+        let test: Test = "not ok 2 - foo".try_into().expect("Parser error");
+        assert_eq!(test.number, Some(2));
+        assert!(Document::try_from("not TAP").is_err());
+    }
+
+    #[test]
+    fn test_parse_many() {
+        let content = concat!(
+            "make[1]: Entering directory\n",
+            "TAP version 14\n",
+            "1..1\n",
+            "ok 1\n",
+            "tap version 13\n",
+            "1..1\n",
+            "# Subtest: sub\n",
+            "    1..1\n",
+            "    ok 1\n",
+            "ok 1 - sub\n",
+        );
+        let docs = Document::parse_many(content).expect("Parser error");
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[1].preamble.version, "13");
+        let Statement::Subtest(subtest) = &docs[1].body[0] else {
+            panic!("Expected a subtest")
+        };
+        assert_eq!(subtest.span.start, content.find("# Subtest").unwrap());
+        assert_eq!(subtest.span.start_line, 7);
+        assert_eq!(
+            docs[1].tests().next().unwrap().span.start,
+            content.find("    ok 1").unwrap() + 4
+        );
+
+        assert!(Document::parse_many("no TAP here\n").unwrap().is_empty());
+        assert!(
+            Document::parse_many(concat!("TAP version 14\n", "1..1\n", "TAP version 14\n"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_document_eq() {
+        let contents = fs::read_to_string("examples/yaml.tap").expect("Failed to read file");
+        let doc = Document::parse_from_str(&contents).expect("Parser error");
+        assert_eq!(doc, OwnedDocument::from(&doc).as_borrowed());
+
+        let other = contents.replacen("not ok 1", "ok 1", 1);
+        assert_ne!(
+            Document::parse_from_str(&contents).expect("Parser error"),
+            Document::parse_from_str(&other).expect("Parser error")
+        );
+    }
+
+    #[test]
+    fn test_comment_statement() {
+        parses_to! {
+            parser: TAPParser,
+            input : "#  a comment # with a hash  ",
+            rule: Rule::comment,
+            tokens: [
+                comment(0, 26, [comment_text(3, 26)])
+            ]
+        }
+    }
+
+    #[test]
+    fn test_comments_preserved() {
+        let contents = fs::read_to_string("examples/cascading.tap").expect("Failed to read file");
+        let options = ParseOptions::new().comments(true);
+        let doc = Document::parse_from_str_with_options(&contents, &options).expect("Parser error");
+        let Statement::Subtest(subtest) = &doc.body[1] else {
+            panic!("Expected a subtest")
+        };
+        assert_eq!(subtest.name, Some("here begins sub-1"));
+
+        let contents = fs::read_to_string("examples/common.tap").expect("Failed to read file");
+        let doc = Document::parse_from_str_with_options(&contents, &options).expect("Parser error");
+        let comments: Vec<_> = doc
+            .body
+            .iter()
+            .filter_map(|s| match s {
+                Statement::Comment(c) => Some(*c),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            comments,
+            [
+                "",
+                "Create a new Board and Tile, then place",
+                "the Tile onto the board.",
+                "",
+            ]
+        );
+    }
+
     #[test]
     fn test_yaml_block() {
         parses_to! {
@@ -1159,6 +3307,426 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_yaml_block_nested_terminators() {
+        parses_to! {
+            parser: TAPParser,
+            input : concat!(
+                "  ---\n",
+                "  text: |\n",
+                "    ...\n",
+                "  ...and more\n",
+                "  ...\n",
+                "  ...\n",
+            ),
+            rule: Rule::yaml_block,
+            tokens: [
+                yaml_block(0, 43, [
+                    yaml(8, 15),
+                    yaml(18, 23),
+                    yaml(26, 37),
+                ])
+            ]
+        }
+    }
+
+    #[test]
+    fn test_yaml_block_terminator_trailing_whitespace() {
+        parses_to! {
+            parser: TAPParser,
+            input : concat!(
+                "  ---\n",
+                "  foo: bar\n",
+                "  ...  \r\n",
+            ),
+            rule: Rule::yaml_block,
+            tokens: [
+                yaml_block(0, 24, [
+                    yaml(8, 16),
+                ])
+            ]
+        }
+    }
+
+    #[test]
+    fn test_tab_indentation() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..1\n",
+            "# Subtest: outer\n",
+            "\t1..2\n",
+            "\tnot ok 1 - boom\n",
+            "\t  ---\n",
+            "\t  message: boom\n",
+            "\t  ...\n",
+            "\t# Subtest: inner\n",
+            "\t    1..1\n",
+            "\t    ok 1\n",
+            "\tok 2 - inner\n",
+            "not ok 1 - outer\n",
+        );
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        let depths: Vec<_> = doc
+            .tests_with_depth()
+            .map(|(depth, t)| (depth, t.effective_number))
+            .collect();
+        assert_eq!(depths, [(1, 1), (2, 1), (1, 2), (0, 1)]);
+        assert_eq!(doc.tests().next().unwrap().yaml, ["message: boom"]);
+
+        /* Inconsistent indentation within a subtest isn't recognized, but doesn't fail either. */
+        let content = "TAP version 14\n1..1\n# Subtest\n\t1..2\n    ok 1\n\tok 2\nok 1\n";
+        assert!(Document::parse_from_str(content).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_number_in_strict_mode() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..2\n",
+            "ok 99999999999\n",
+            "pragma +strict\n",
+            "ok 2\n",
+        );
+        let doc = Document::parse_from_str_with_pragmas(content).expect("Parser error");
+        assert!(doc.tests().next().unwrap().has_invalid_number());
+        assert_eq!(doc.tests().nth(1).unwrap().effective_number, 2);
+
+        let content = content.replace("ok 2", "ok 99999999999");
+        let err = Document::parse_from_str_with_pragmas(&content).unwrap_err();
+        assert!(matches!(err, TapError::NumberParse(_)));
+    }
+
+    #[test]
+    fn test_limits() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..2\n",
+            "# Subtest: sub\n",
+            "    1..1\n",
+            "    ok 1\n",
+            "ok 1 - sub\n",
+            "ok 2\n",
+        );
+        let parse =
+            |options: ParseOptions| Document::parse_from_str_with_options(content, &options);
+        assert!(parse(ParseOptions::new().max_bytes(content.len())).is_ok());
+        assert!(matches!(
+            parse(ParseOptions::new().max_bytes(content.len() - 1)),
+            Err(TapError::LimitExceeded {
+                limit: Limit::Bytes,
+                max: 68,
+            })
+        ));
+        assert!(parse(ParseOptions::new().max_statements(4)).is_ok());
+        let err = parse(ParseOptions::new().max_statements(3)).unwrap_err();
+        assert!(matches!(
+            err,
+            TapError::LimitExceeded {
+                limit: Limit::Statements,
+                max: 3,
+            }
+        ));
+        assert_eq!(err.to_string(), "Content exceeds the limit of 3 statements");
+    }
+
+    #[test]
+    fn test_source_spans() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..1\n",
+            "# Subtest: sub\n",
+            "    # dropped comment\n",
+            "    ok 1\n",
+            "    1..1\n",
+            "ok 1 - sub\n",
+        );
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        assert_eq!(&content[doc.span.start..doc.span.end], content);
+        let Statement::Subtest(subtest) = &doc.body[0] else {
+            panic!("Expected a subtest, got {:?}", doc.body[0]);
+        };
+        assert_eq!(
+            &content[subtest.span.start..subtest.span.end],
+            "# Subtest: sub\n    # dropped comment\n    ok 1\n    1..1"
+        );
+    }
+
+    #[test]
+    fn test_effective_number() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..6\n",
+            "ok\n",
+            "ok\n",
+            "ok 5\n",
+            "# Subtest\n",
+            "    ok\n",
+            "    ok 3\n",
+            "    ok\n",
+            "    1..3\n",
+            "ok\n",
+            "ok 2\n",
+        );
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        let numbers: Vec<_> = doc.tests().map(|t| t.effective_number).collect();
+        assert_eq!(numbers, [1, 2, 5, 1, 3, 4, 6, 2]);
+    }
+
+    #[test]
+    fn test_effective_number_saturates() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..2\n",
+            "ok 2147483647\n",
+            "ok\n",
+            "# Subtest\n",
+            "    1..1\n",
+            "    ok 1\n",
+        );
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        let numbers: Vec<_> = doc.tests().map(|t| t.effective_number).collect();
+        assert_eq!(numbers, [i32::MAX, i32::MAX, 1]);
+        assert!(!doc.check_numbering().is_empty());
+
+        let mut parser = StreamingParser::new();
+        let mut statements = Vec::new();
+        for line in content.lines() {
+            statements.extend(parser.push_line(line).expect("Parser error"));
+        }
+        statements.extend(parser.finish().expect("Parser error"));
+        let expected: Vec<_> = doc.body.iter().map(OwnedStatement::from).collect();
+        assert_eq!(statements, expected);
+
+        #[cfg(feature = "csv")]
+        assert!(doc.to_csv().contains("2147483647.1"));
+        #[cfg(feature = "serde")]
+        assert!(doc.to_toml().contains("2147483647.1"));
+        #[cfg(feature = "subunit")]
+        assert!(!doc.to_subunit().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_statement_json() {
+        let golden = [
+            (
+                concat!("ok 1 - foo # skip bar\n", "  ---\n", "  a: b\n", "  ...\n"),
+                r#"{"test":{"result":true,"number":1,"effective_number":1,"description":"foo","dash":true,"directive":{"key":"Skip","reason":"bar"},"yaml":["a: b"],"span":{"start":0,"end":40,"start_line":1,"start_col":1,"end_line":4,"end_col":6}}}"#,
+            ),
+            ("hello world", r#"{"anything":"hello world"}"#),
+            (
+                "Bail out! boom",
+                r#"{"bail_out":{"reason":"boom","span":{"start":0,"end":14,"start_line":1,"start_col":1,"end_line":1,"end_col":15}}}"#,
+            ),
+            (
+                "pragma +strict",
+                r#"{"pragma":{"flag":true,"option":"strict","span":{"start":0,"end":14,"start_line":1,"start_col":1,"end_line":1,"end_col":15}}}"#,
+            ),
+            (
+                concat!("# Subtest: foo\n", "    1..1\n", "    not ok 1\n"),
+                r#"{"subtest":{"name":"foo","plan":{"first":1,"last":1,"reason":null},"body":[{"test":{"result":false,"number":1,"effective_number":1,"description":null,"dash":false,"directive":null,"yaml":[],"span":{"start":28,"end":36,"start_line":3,"start_col":5,"end_line":3,"end_col":13}}}],"span":{"start":0,"end":37,"start_line":1,"start_col":1,"end_line":4,"end_col":1}}}"#,
+            ),
+        ];
+
+        for (content, expected) in golden {
+            let statement = Statement::parse_from_str(content).expect("Parser error");
+            let json = serde_json::to_string(&statement).expect("Serializer error");
+            assert_eq!(json, expected);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_yaml_value() {
+        let contents = fs::read_to_string("examples/yaml.tap").expect("Failed to read file");
+        let doc = Document::parse_from_str(&contents).expect("Parser error");
+        let Statement::Test(test) = &doc.body[0] else {
+            panic!("Expected a test")
+        };
+        let yaml = test.yaml_value().expect("YAML error");
+        assert_eq!(yaml["found"]["address"], serde_yaml::Value::Null);
+        assert_eq!(yaml["wanted"]["address"], "85.193.201.85");
+        assert_eq!(yaml["at"]["file"], "test/dns-resolve.c");
+    }
+
+    #[test]
+    fn test_yaml_nested_indentation() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..2\n",
+            "# Subtest: sub\n",
+            "    1..2\n",
+            "    not ok 1 - inner\n",
+            "      ---\n",
+            "      message: boom\n",
+            "      at:\n",
+            "        line: 3\n",
+            "      ...\n",
+            "    ok 2 - not a block\n",
+            "    ---\n",
+            "    ...\n",
+            "not ok 1 - sub\n",
+            "not ok 2 - four\n",
+            "    ---\n",
+            "    message: four\n",
+            "    ...\n",
+        );
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        let yaml: Vec<_> = doc.tests().map(|t| t.yaml.clone()).collect();
+        assert_eq!(
+            yaml,
+            [
+                vec!["message: boom", "at:", "  line: 3"],
+                vec![],
+                vec![],
+                vec!["message: four"],
+            ]
+        );
+        let Statement::Subtest(subtest) = &doc.body[0] else {
+            panic!("Expected a subtest");
+        };
+        /* A block must be indented deeper than its test line. */
+        assert!(matches!(subtest.body[2], Statement::Anything("---")));
+    }
+
+    #[test]
+    fn test_yaml_raw() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..1\n",
+            "# Subtest: nested\n",
+            "    1..1\n",
+            "    not ok 1 - foo\n",
+            "      ---\n",
+            "      message: |\n",
+            "        indented\n",
+            "      ...  \n",
+            "ok 1 - nested\n",
+        );
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        let test = doc.tests().next().unwrap();
+        assert_eq!(test.yaml, ["message: |", "  indented"]);
+        assert_eq!(
+            test.yaml_raw,
+            Some("---\n      message: |\n        indented\n      ...")
+        );
+        assert_eq!(doc.tests().nth(1).unwrap().yaml_raw, None);
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let nested = |depth: usize| {
+            let indent = |level: usize| "    ".repeat(level);
+            let mut content = String::from("TAP version 14\n1..1\n");
+            for level in 0..depth {
+                content += &format!("{}# Subtest: level {}\n", indent(level), level + 1);
+                content += &format!("{}    1..1\n", indent(level));
+            }
+            content += &format!("{}ok 1 - leaf\n", indent(depth));
+            for level in (0..depth).rev() {
+                content += &format!("{}ok 1 - level {}\n", indent(level), level + 1);
+            }
+            content
+        };
+
+        let content = nested(8);
+        let doc = Document::parse_from_str(&content).expect("Parser error");
+        let (depth, leaf) = doc.tests_with_depth().next().unwrap();
+        assert_eq!(depth, 8);
+        assert_eq!(leaf.description.as_deref(), Some("leaf"));
+
+        let options = ParseOptions::new().max_depth(4);
+        assert!(matches!(
+            Document::parse_from_str_with_options(&content, &options),
+            Err(TapError::MaxDepthExceeded {
+                max_depth: 4,
+                line: 12
+            })
+        ));
+
+        let content = nested(10_000);
+        assert!(matches!(
+            Document::parse_from_str(&content),
+            Err(TapError::MaxDepthExceeded { max_depth: 64, .. })
+        ));
+        assert!(matches!(
+            Statement::parse_from_str(&content[20..]),
+            Err(TapError::MaxDepthExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn test_metadata() {
+        let contents = fs::read_to_string("examples/metadata.tap").expect("Failed to read file");
+        let options = ParseOptions::new().comment_metadata(true);
+        let doc = Document::parse_from_str_with_options(&contents, &options).expect("Parser error");
+
+        let tests: Vec<_> = doc
+            .body
+            .iter()
+            .flat_map(|s| match s {
+                Statement::Test(t) => vec![t],
+                Statement::Subtest(s) => s
+                    .body
+                    .iter()
+                    .filter_map(|s| match s {
+                        Statement::Test(t) => Some(t),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => vec![],
+            })
+            .collect();
+        assert_eq!(tests.len(), 5);
+        assert_eq!(
+            tests[0].metadata,
+            BTreeMap::from([("owner", "alice"), ("tags", "smoke")])
+        );
+        assert!(tests[1].metadata.is_empty());
+        assert_eq!(tests[2].metadata, BTreeMap::from([("owner", "bob")]));
+        assert_eq!(tests[3].metadata, BTreeMap::from([("owner", "carol")]));
+        assert_eq!(tests[4].metadata, BTreeMap::from([("owner", "dave")]));
+
+        let doc = Document::parse_from_str(&contents).expect("Parser error");
+        let Statement::Test(test) = &doc.body[0] else {
+            panic!("Expected a test")
+        };
+        assert!(test.metadata.is_empty());
+    }
+
+    #[test]
+    fn test_strict_pragma_scopes() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..2\n",
+            "pragma +strict\n",
+            "# Subtest: sub\n",
+            "    1..1\n",
+            "    lenient garbage\n",
+            "    ok 1\n",
+            "ok 1 - sub\n",
+            "pragma -strict\n",
+            "lenient garbage\n",
+            "ok 2\n",
+        );
+        assert!(Document::parse_from_str_with_pragmas(content).is_ok());
+
+        let content = concat!(
+            "TAP version 14\n",
+            "1..1\n",
+            "# Subtest: sub\n",
+            "    1..1\n",
+            "    pragma +strict\n",
+            "    strict garbage\n",
+            "    ok 1\n",
+            "ok 1 - sub\n",
+        );
+        assert!(matches!(
+            Document::parse_from_str_with_pragmas(content),
+            Err(TapError::NonTapLine(l)) if l == "strict garbage"
+        ));
+    }
+
     #[test]
     fn test_common() {
         let contents = fs::read_to_string("examples/common.tap").expect("Failed to read file");
@@ -1176,6 +3744,10 @@ mod tests {
                         first(15, 16), last(18, 19)
                     ]),
                     body(19, 318, [
+                        comment(20, 21, [comment_text(21, 21)]),
+                        comment(22, 63, [comment_text(24, 63)]),
+                        comment(64, 90, [comment_text(66, 90)]),
+                        comment(91, 92, [comment_text(92, 92)]),
                         test(93, 131, [
                             result(93, 95),
                             number(96, 97),
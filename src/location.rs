@@ -0,0 +1,219 @@
+//! Source locations reported by the YAML diagnostics of failing tests.
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::Test;
+
+/// A location in the source of the system under test, as reported by the YAML diagnostics of a [`Test`], see
+/// [`Test::diagnostic_location`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Location<'a> {
+    /// Path of the file, exactly as reported.
+    pub file: Option<&'a str>,
+    /// 1-based line.
+    pub line: Option<u32>,
+    /// 1-based column.
+    pub column: Option<u32>,
+}
+
+impl<'a> Location<'a> {
+    /// Record `value` under `key`, unless another value was recorded for it already. Unknown keys are ignored.
+    fn set(&mut self, key: &str, value: &'a str) {
+        let value = unquote(value);
+        let is = |keys: &[&str]| keys.iter().any(|k| k.eq_ignore_ascii_case(key));
+        if is(&["file", "filename", "path"]) {
+            self.file = self.file.or(Some(value).filter(|v| !v.is_empty()));
+        } else if is(&["line"]) {
+            self.line = self.line.or(value.parse().ok());
+        } else if is(&["column", "col"]) {
+            self.column = self.column.or(value.parse().ok());
+        }
+    }
+
+    /// Parse an inline `at` value, either a flow mapping like `{ file: foo.c, line: 12 }` or a string like `foo.c:12`
+    /// or `foo.c:12:3`.
+    fn set_inline(&mut self, value: &'a str) {
+        let value = unquote(value);
+        if let Some(mapping) = value.strip_prefix('{').and_then(|v| v.strip_suffix('}')) {
+            for (key, value) in mapping.split(',').filter_map(|entry| entry.split_once(':')) {
+                self.set(key.trim(), value);
+            }
+            return;
+        }
+        let mut parts = value.rsplitn(3, ':');
+        let (last, middle, first) = (parts.next(), parts.next(), parts.next());
+        let number = |part: Option<&str>| part.and_then(|p| p.trim().parse::<u32>().ok());
+        match (number(middle), number(last)) {
+            (Some(line), Some(column)) if first.is_some() => {
+                self.file = self.file.or(first);
+                self.line = self.line.or(Some(line));
+                self.column = self.column.or(Some(column));
+            }
+            (_, Some(line)) if middle.is_some() => {
+                let file = value.rsplit_once(':').map(|(file, _)| file);
+                self.file = self.file.or(file);
+                self.line = self.line.or(Some(line));
+            }
+            _ => self.file = self.file.or(Some(value).filter(|v| !v.is_empty())),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.file.is_none() && self.line.is_none()
+    }
+}
+
+/// Strip surrounding whitespace and a matching pair of quotes off `value`.
+pub(crate) fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    ['"', '\'']
+        .iter()
+        .find_map(|q| value.strip_prefix(*q)?.strip_suffix(*q))
+        .unwrap_or(value)
+}
+
+/// Split a YAML line into its indentation and, if it holds a `key: value` pair, the trimmed key and raw value.
+pub(crate) fn split(line: &str) -> (usize, Option<(&str, &str)>) {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+    let pair = trimmed.split_once(':').filter(|(key, value)| {
+        !key.is_empty() && (value.is_empty() || value.starts_with([' ', '\t']))
+    });
+    (indent, pair.map(|(key, value)| (key.trim_end(), value)))
+}
+
+impl<'a> Test<'a> {
+    /// Look up the source location reported by the test's YAML block, e.g. for an IDE to jump to a failing assertion.
+    ///
+    /// The location is taken from an `at` key, holding either a nested mapping, a flow mapping or a `file:line[:column]`
+    /// string, or else from top-level `file`, `line` and `column` keys. `filename` and `path` are accepted for the file,
+    /// `col` for the column. Keys are matched case-insensitively and values may be quoted. Any other content is
+    /// ignored, no YAML parser is involved. Yields [`None`] if neither a file nor a line is found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::{Location, Test};
+    ///
+    /// let content = concat!(
+    ///     "not ok 1 - Resolve address\n",
+    ///     "  ---\n",
+    ///     "  message: hostname not found\n",
+    ///     "  at:\n",
+    ///     "    file: test/dns-resolve.c\n",
+    ///     "    line: 142\n",
+    ///     "  ...\n",
+    /// );
+    /// let test = Test::parse_from_str(content).expect("Parser error");
+    /// assert_eq!(
+    ///     test.diagnostic_location(),
+    ///     Some(Location { file: Some("test/dns-resolve.c"), line: Some(142), column: None })
+    /// );
+    ///
+    /// let content = "not ok 2\n  ---\n  at: 'src/lib.rs:12:5'\n  ...\n";
+    /// let location = Test::parse_from_str(content).unwrap().diagnostic_location().unwrap();
+    /// assert_eq!((location.file, location.line, location.column), (Some("src/lib.rs"), Some(12), Some(5)));
+    ///
+    /// assert_eq!(Test::parse_from_str("not ok 3").unwrap().diagnostic_location(), None);
+    /// ```
+    pub fn diagnostic_location(&self) -> Option<Location<'a>> {
+        let mut at = Location::default();
+        let mut top = Location::default();
+        let base = self
+            .yaml
+            .iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| split(line).0)
+            .min()
+            .unwrap_or(0);
+        /* Indentation of the `at` key while inside its nested mapping. */
+        let mut in_at = None;
+        for line in &self.yaml {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (indent, pair) = split(line);
+            if in_at.is_some_and(|at_indent| indent > at_indent) {
+                if let Some((key, value)) = pair {
+                    at.set(key, value);
+                }
+                continue;
+            }
+            in_at = None;
+            match pair {
+                Some((key, value)) if indent == base && key.eq_ignore_ascii_case("at") => {
+                    if value.trim().is_empty() {
+                        in_at = Some(indent);
+                    } else {
+                        at.set_inline(value);
+                    }
+                }
+                Some((key, value)) if indent == base => top.set(key, value),
+                _ => {}
+            }
+        }
+        [at, top]
+            .iter()
+            .copied()
+            .find(|location| !location.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(yaml: &[&'static str]) -> Option<Location<'static>> {
+        Test {
+            yaml: yaml.to_vec(),
+            ..Test::parse_from_str("not ok").unwrap()
+        }
+        .diagnostic_location()
+    }
+
+    #[test]
+    fn test_diagnostic_location_variants() {
+        let expected = Location {
+            file: Some("t/foo.t"),
+            line: Some(7),
+            column: Some(3),
+        };
+        assert_eq!(
+            location(&["file: t/foo.t", "line: 7", "column: 3"]),
+            Some(expected)
+        );
+        assert_eq!(
+            location(&["at: { file: \"t/foo.t\", line: 7, col: 3 }"]),
+            Some(expected)
+        );
+        assert_eq!(location(&["At: t/foo.t:7:3"]), Some(expected));
+        assert_eq!(
+            location(&["AT:", "  FILE: 't/foo.t'", "  Line: '7'", "  column: 3"]),
+            Some(expected)
+        );
+        /* `at` takes precedence over top-level keys, nested keys of other mappings are ignored. */
+        assert_eq!(
+            location(&[
+                "file: other.t",
+                "at:",
+                "  file: t/foo.t",
+                "  line: 7",
+                "data:",
+                "  line: 9"
+            ]),
+            Some(Location {
+                file: Some("t/foo.t"),
+                line: Some(7),
+                column: None,
+            })
+        );
+        assert_eq!(
+            location(&["at: C:\\t\\foo.t:7"]).and_then(|l| l.file),
+            Some("C:\\t\\foo.t")
+        );
+        assert_eq!(location(&["message: boom", "line: seven"]), None);
+        assert_eq!(location(&[]), None);
+    }
+}
@@ -1,5 +1,53 @@
-use clap::Parser as ClapParser;
-use std::{fs, io::Read};
+use anyhow::{bail, Context, Result};
+use clap::{Parser as ClapParser, ValueEnum};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::{
+    env, fs,
+    io::{self, IsTerminal, Read},
+    path::{Path, PathBuf},
+    process,
+};
+use tapconsooomer::{Document, Key, OmitNull, ParseOptions, Test, YamlAsObject};
+
+/// Exit code if any test failed.
+const EXIT_FAILED: i32 = 2;
+/// Exit code if the TAP producer bailed out.
+const EXIT_BAILED_OUT: i32 = 3;
+
+/// Controls when colored output is emitted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ColorChoice {
+    /// Color output if stderr is a terminal and `NO_COLOR` is not set.
+    Auto,
+    /// Always color output.
+    Always,
+    /// Never color output.
+    Never,
+}
+
+impl ColorChoice {
+    fn enabled(self) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto => {
+                env::var_os("NO_COLOR").map_or(true, |v| v.is_empty()) && io::stderr().is_terminal()
+            }
+        }
+    }
+}
+
+/// Serialization format of the parser result.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Pretty-printed JSON.
+    Json,
+    /// YAML.
+    Yaml,
+    /// Pretty-printed TOML.
+    Toml,
+}
 
 #[derive(ClapParser, Debug)]
 #[clap(
@@ -7,35 +55,318 @@ use std::{fs, io::Read};
     version,
     about,
     long_about = concat!("Reads a given Test Anything Protocol (TAP) file ",
-    "and prints the JSON-formatted parser result to stdout. If FILE is ",
+    "and prints the parser result to stdout, formatted as JSON unless ",
+    "requested otherwise. If FILE is ",
     "omitted, TAP input is read from stdin. Parsing only comences after ",
-    "encountering an EOF. Only complete TAP files are supported.")
+    "encountering an EOF. Only complete TAP files are supported.\n\n",
+    "Given several FILEs or a glob pattern, the files are parsed in parallel ",
+    "and the results are emitted as an array of documents, ordered by path. ",
+    "TOML output nests this array under a `documents` key."),
+    after_long_help = concat!("Exit status:\n",
+    "  0  All tests passed\n",
+    "  1  The TAP input could not be read or parsed\n",
+    "  2  At least one test failed, not counting TODO failures unless --strict-exit is given\n",
+    "  3  The TAP producer bailed out")
 )]
 struct Cli {
-    /// Path to TAP input file.
+    /// Paths or glob patterns of TAP input files. An existing path is taken literally, even if it looks like a glob
+    /// pattern.
     #[clap(value_parser, value_name = "FILE")]
-    tap_file: Option<String>,
+    tap_files: Vec<String>,
+
+    /// Output format of the parser result.
+    #[clap(short, long, value_enum, default_value_t = Format::Json)]
+    format: Format,
+
+    /// Emit each test's YAML block as a structured object instead of a list of raw lines, where the block is valid
+    /// YAML. TOML output leaves out null values inside the blocks, as TOML can't represent them.
+    #[clap(long)]
+    yaml_as_object: bool,
+
+    /// Leave out absent optional fields, such as the number or description of a test, instead of emitting `null`.
+    #[clap(long)]
+    omit_null: bool,
+
+    /// Preserve standalone comment lines as `comment` statements.
+    #[clap(long)]
+    comments: bool,
+
+    /// When to use colored error output. `auto` honors the `NO_COLOR` environment variable.
+    #[clap(long, value_enum, value_name = "WHEN", default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Print a compact summary of the test results, followed by any failing tests, instead of the parser result. Ends
+    /// with a `Result: PASS` or `Result: FAIL (<failed> of <tests>)` line like `prove`, preceded by a
+    /// `Files=<files>, Tests=<tests>` line given several files.
+    #[clap(long, conflicts_with_all = ["format", "yaml_as_object", "omit_null"])]
+    summary: bool,
+
+    /// Count failing tests with a TODO directive as failures when determining the exit status.
+    #[clap(long)]
+    strict_exit: bool,
+
+    /// Prune the parser result down to the failing tests and the subtests containing them. Plans are kept as is.
+    #[clap(long, conflicts_with = "summary")]
+    failed_only: bool,
+
+    /// Decompress the TAP input with gzip. Input starting with the gzip magic bytes, e.g. a `.tap.gz` file, is
+    /// decompressed regardless.
+    #[cfg(feature = "gzip")]
+    #[clap(long)]
+    gzip: bool,
+}
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const RESET: &str = "\x1b[0m";
+
+/// Print `error` to stderr, highlighting the `error:` label, the source gutter and any carets if `color` is set.
+fn report(error: &anyhow::Error, color: bool) {
+    let rendered = format!("{:#}", error);
+    if color {
+        eprintln!("{}error{}: {}", RED, RESET, colorize(&rendered));
+    } else {
+        eprintln!("error: {}", rendered);
+    }
+}
+
+/// Colorize a rendered pest error, highlighting the gutter (`-->`, `|`, `=`) and carets (`^---`).
+fn colorize(rendered: &str) -> String {
+    rendered
+        .lines()
+        .map(|line| match line.find(['|', '=', '-']) {
+            Some(i) if line[..i].trim().chars().all(|c| c.is_ascii_digit()) => {
+                let (gutter, rest) =
+                    line.split_at(i + if line[i..].starts_with("-->") { 3 } else { 1 });
+                if !rest.trim().is_empty() && rest.trim().chars().all(|c| matches!(c, '^' | '-')) {
+                    format!("{}{}{}{}{}{}", BLUE, gutter, RESET, RED, rest, RESET)
+                } else {
+                    format!("{}{}{}{}", BLUE, gutter, RESET, rest)
+                }
+            }
+            _ => line.to_owned(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Wrapper giving several documents a top-level key, as TOML can't express a top-level array.
+#[derive(Serialize)]
+struct Documents<T> {
+    documents: T,
+}
+
+fn serialize<T: Serialize + ?Sized>(value: &T, format: Format) -> Result<String> {
+    Ok(match format {
+        Format::Json => serde_json::to_string_pretty(value)?,
+        Format::Yaml => serde_yaml::to_string(value)?,
+        Format::Toml => toml::to_string_pretty(value)?,
+    })
+}
+
+/// Serialize `value` as requested by `cli`. TOML leaves out absent fields anyway, so null values are omitted for it
+/// throughout, including those inside YAML blocks.
+fn emit<T: Serialize + ?Sized>(value: &T, cli: &Cli) -> Result<String> {
+    let omit_null = cli.omit_null || cli.format == Format::Toml;
+    match (cli.yaml_as_object, omit_null) {
+        (true, true) => serialize(&OmitNull(&YamlAsObject(value)), cli.format),
+        (true, false) => serialize(&YamlAsObject(value), cli.format),
+        (false, true) => serialize(&OmitNull(value), cli.format),
+        (false, false) => serialize(value, cli.format),
+    }
+}
+
+/// Render the [`tapconsooomer::Summary`] of `document`, followed by each failing test, indented by its nesting depth.
+fn summarize(document: &Document) -> String {
+    let mut lines = vec![document.summary().to_string()];
+    for (depth, test) in document.tests_with_depth().filter(|(_, t)| !t.result) {
+        let test = Test {
+            yaml: Vec::new(),
+            yaml_raw: None,
+            ..test.clone()
+        };
+        lines.push(format!("{}{}", "  ".repeat(depth), test));
+    }
+    lines.join("\n")
+}
+
+/// Render the final lines of `prove`, e.g. `Result: FAIL (2 of 10)`, for `documents` read from several files unless
+/// `single`. Failing tests are counted like [`exit_code`] does.
+fn prove_footer(documents: &[Document], strict: bool, single: bool) -> String {
+    let tests: usize = documents.iter().map(|d| d.summary().total).sum();
+    let failed: usize = documents.iter().map(|d| failures(d, strict)).sum();
+    let mut lines = Vec::new();
+    if !single {
+        lines.push(format!("Files={}, Tests={}", documents.len(), tests));
+    }
+    if failed == 0 && !documents.iter().any(|d| d.summary().bailed_out) {
+        lines.push("Result: PASS".to_owned());
+    } else {
+        lines.push(format!("Result: FAIL ({} of {})", failed, tests));
+    }
+    lines.join("\n")
+}
+
+/// Count the failing tests of `document`, not counting TODO failures unless `strict`.
+fn failures(document: &Document, strict: bool) -> usize {
+    document
+        .tests()
+        .filter(|t| !t.result && (strict || !matches!(&t.directive, Some(d) if d.key == Key::Todo)))
+        .count()
+}
+
+/// Determine the exit code for a successfully parsed `document`.
+fn exit_code(document: &Document, strict: bool) -> i32 {
+    if document.summary().bailed_out {
+        EXIT_BAILED_OUT
+    } else if failures(document, strict) > 0 {
+        EXIT_FAILED
+    } else {
+        0
+    }
+}
+
+/// Whether `arg` is a glob pattern rather than a plain path. An existing file is taken literally, even if its name
+/// holds glob characters, e.g. `results[1].tap`.
+fn is_pattern(arg: &str) -> bool {
+    arg.contains(['*', '?', '[']) && !Path::new(arg).exists()
+}
+
+/// Expand `args` into a sorted, deduplicated list of paths.
+fn expand(args: &[String]) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for arg in args {
+        if is_pattern(arg) {
+            let before = paths.len();
+            for path in glob::glob(arg).with_context(|| format!("Invalid glob pattern, {}", arg))? {
+                paths
+                    .push(path.with_context(|| format!("Failed to expand glob pattern, {}", arg))?);
+            }
+            if paths.len() == before {
+                bail!("No files match glob pattern, {}", arg);
+            }
+        } else {
+            paths.push(PathBuf::from(arg));
+        }
+    }
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+/// Magic bytes starting a gzip stream.
+#[cfg(feature = "gzip")]
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Decode the raw bytes of a TAP input as UTF-8. With the `gzip` feature, gzip compressed input is decompressed first,
+/// if forced by `gzip` or detected by its magic bytes.
+fn decode(bytes: Vec<u8>, gzip: bool) -> Result<String> {
+    #[cfg(feature = "gzip")]
+    if gzip || bytes.starts_with(&GZIP_MAGIC) {
+        let mut content = String::new();
+        flate2::read::GzDecoder::new(&bytes[..])
+            .read_to_string(&mut content)
+            .context("Failed to decompress gzip input")?;
+        return Ok(content);
+    }
+    #[cfg(not(feature = "gzip"))]
+    let _ = gzip;
+    String::from_utf8(bytes).context("Input is not valid UTF-8")
+}
+
+/// Read all TAP input, either from the files given by `args` or from stdin. Returns the path and content of each input.
+fn read(args: &[String], gzip: bool) -> Result<Vec<(PathBuf, String)>> {
+    if args.is_empty() {
+        let mut buf = Vec::with_capacity(4096);
+        let content = io::stdin()
+            .read_to_end(&mut buf)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| decode(buf, gzip))
+            .context("Failed to read from stdin")?;
+        return Ok(vec![(PathBuf::from("-"), content)]);
+    }
+    expand(args)?
+        .par_iter()
+        .map(|path| {
+            fs::read(path)
+                .map_err(anyhow::Error::from)
+                .and_then(|bytes| decode(bytes, gzip))
+                .map(|content| (path.clone(), content))
+                .with_context(|| format!("Failed to read file, {}", path.display()))
+        })
+        .collect()
+}
+
+fn run(cli: Cli) -> Result<i32> {
+    #[cfg(feature = "gzip")]
+    let gzip = cli.gzip;
+    #[cfg(not(feature = "gzip"))]
+    let gzip = false;
+    let inputs = read(&cli.tap_files, gzip)?;
+    let options = ParseOptions::new().comments(cli.comments);
+    let mut documents = inputs
+        .par_iter()
+        .map(|(path, content)| {
+            Document::parse_from_str_with_options(content, &options).with_context(|| {
+                if cli.tap_files.is_empty() {
+                    "Failed to parse stdin".to_owned()
+                } else {
+                    format!("Failed to parse file, {}", path.display())
+                }
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let codes: Vec<_> = documents
+        .iter()
+        .map(|document| exit_code(document, cli.strict_exit))
+        .collect();
+    if cli.failed_only {
+        documents.iter_mut().for_each(Document::retain_failures);
+    }
+
+    /* A single plain FILE keeps the single document output. */
+    let single =
+        matches!(&cli.tap_files[..], [] | [_] if !cli.tap_files.iter().any(|f| is_pattern(f)));
+    let output = if cli.summary && single {
+        Ok(format!(
+            "{}\n{}",
+            summarize(&documents[0]),
+            prove_footer(&documents, cli.strict_exit, single)
+        ))
+    } else if cli.summary {
+        let mut lines: Vec<_> = inputs
+            .iter()
+            .zip(&documents)
+            .map(|((path, _), document)| format!("{}: {}", path.display(), summarize(document)))
+            .collect();
+        lines.push(prove_footer(&documents, cli.strict_exit, single));
+        Ok(lines.join("\n"))
+    } else if single {
+        emit(&documents[0], &cli)
+    } else if cli.format == Format::Toml {
+        emit(
+            &Documents {
+                documents: &documents,
+            },
+            &cli,
+        )
+    } else {
+        emit(&documents, &cli)
+    }
+    .context("Failed to serialize TAP document")?;
+    println!("{}", output.trim_end());
+    Ok(codes.into_iter().max().unwrap_or(0))
 }
 
 fn main() {
     let cli = Cli::parse();
+    let color = cli.color.enabled();
 
-    let content = cli.tap_file.map_or_else(
-        || {
-            let mut buf = String::with_capacity(4096);
-            std::io::stdin()
-                .read_to_string(&mut buf)
-                .map(move |_| buf)
-                .unwrap_or_else(|_| panic!("Failed to read from stdin"))
-        },
-        |file| {
-            fs::read_to_string(&file).unwrap_or_else(|_| panic!("Failed to read file, {}", &file))
-        },
-    );
-    let document =
-        tapconsooomer::Document::parse_from_str(&content).expect("Failed to parse TAP document");
-    println!(
-        "{}",
-        serde_json::to_string_pretty(&document).expect("Failed to serialize TAP document")
-    )
+    match run(cli) {
+        Ok(code) => process::exit(code),
+        Err(error) => {
+            report(&error, color);
+            process::exit(1);
+        }
+    }
 }
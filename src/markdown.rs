@@ -0,0 +1,143 @@
+//! Markdown export, e.g. for bots posting test results into pull request threads.
+
+use alloc::{format, string::String};
+use core::fmt::Write;
+
+use crate::{validate::resolve_numbers, Document, Key, Statement};
+
+/// Escape `s` for use in a Markdown table cell.
+fn escape(s: &str) -> String {
+    s.trim().replace('\\', "\\\\").replace('|', "\\|")
+}
+
+/// Append a table of the tests in `statements` to `md`, followed by a nested bullet section per subtest. Every line is
+/// prefixed by `indent`.
+fn write_section(md: &mut String, statements: &[Statement], indent: &str) {
+    let numbers = resolve_numbers(statements);
+    if !numbers.is_empty() {
+        let _ = writeln!(
+            md,
+            "{}| Number | Result | Description | Directive reason |",
+            indent
+        );
+        let _ = writeln!(md, "{}| ---: | :---: | --- | --- |", indent);
+        for (number, test) in numbers {
+            let result = match &test.directive {
+                Some(directive) if directive.key == Key::Skip => "⏭️",
+                _ if test.result => "✅",
+                _ => "❌",
+            };
+            let _ = writeln!(
+                md,
+                "{}| {} | {} | {} | {} |",
+                indent,
+                number,
+                result,
+                test.description.as_deref().map(escape).unwrap_or_default(),
+                test.directive
+                    .as_ref()
+                    .and_then(|d| d.reason)
+                    .map(escape)
+                    .unwrap_or_default()
+            );
+        }
+        md.push('\n');
+    }
+
+    for statement in statements {
+        if let Statement::Subtest(subtest) = statement {
+            match subtest.name {
+                Some(name) => {
+                    let _ = writeln!(md, "{}- **Subtest: {}**", indent, name.trim());
+                }
+                None => {
+                    let _ = writeln!(md, "{}- **Subtest**", indent);
+                }
+            }
+            md.push('\n');
+            write_section(md, &subtest.body, &format!("{}  ", indent));
+        }
+    }
+}
+
+impl Document<'_> {
+    /// Render the document as Markdown.
+    ///
+    /// A bold header line holds the [`crate::Summary`] of the whole document. It is followed by a table of the
+    /// top-level [`crate::Test`]s, with a ✅ or ❌ result, or ⏭️ for tests with a [`Key::Skip`] directive. Each
+    /// [`crate::Subtest`] renders as a nested bullet section, holding a table of its own.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..3\n",
+    ///     "ok 1 - foo()\n",
+    ///     "not ok 2 - bar()\n",
+    ///     "ok 3 - baz() # SKIP unsupported\n",
+    /// );
+    /// let md = Document::parse_from_str(content).expect("Parser error").to_markdown();
+    /// assert!(md.starts_with("**3 tests, 2 passed, 1 failed, 1 skipped, 0 todo**\n"));
+    /// assert!(md.contains("| 2 | ❌ | bar() |  |\n"));
+    /// assert!(md.contains("| 3 | ⏭️ | baz() | unsupported |\n"));
+    /// ```
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!("**{}**\n\n", self.summary());
+        write_section(&mut md, &self.body, "");
+        md.truncate(md.trim_end().len());
+        md.push('\n');
+        md
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_nested() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..2\n",
+            "# Subtest: outer\n",
+            "    1..2\n",
+            "    # Subtest\n",
+            "        1..1\n",
+            "        not ok 1 - a | b # TODO later\n",
+            "    ok 1\n",
+            "    ok 2 - inner # SKIP not today\n",
+            "ok 1 - outer\n",
+            "not ok 2 - last\n",
+        );
+        let md = Document::parse_from_str(content)
+            .expect("Parser error")
+            .to_markdown();
+        assert_eq!(
+            md,
+            concat!(
+                "**5 tests, 3 passed, 2 failed, 1 skipped, 1 todo**\n",
+                "\n",
+                "| Number | Result | Description | Directive reason |\n",
+                "| ---: | :---: | --- | --- |\n",
+                "| 1 | ✅ | outer |  |\n",
+                "| 2 | ❌ | last |  |\n",
+                "\n",
+                "- **Subtest: outer**\n",
+                "\n",
+                "  | Number | Result | Description | Directive reason |\n",
+                "  | ---: | :---: | --- | --- |\n",
+                "  | 1 | ✅ |  |  |\n",
+                "  | 2 | ⏭️ | inner | not today |\n",
+                "\n",
+                "  - **Subtest**\n",
+                "\n",
+                "    | Number | Result | Description | Directive reason |\n",
+                "    | ---: | :---: | --- | --- |\n",
+                "    | 1 | ❌ | a \\| b | later |\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! Combining several [`Document`]s into one, e.g. the partial outputs of a test suite sharded across machines.
+
+use alloc::{format, vec::Vec};
+use core::convert::TryFrom;
+
+use crate::{
+    Document, OwnedDocument, OwnedPlan, OwnedPreamble, OwnedStatement, Span, Warning,
+    WarningCategory,
+};
+
+impl Document<'_> {
+    /// Merge `docs` into a single [`OwnedDocument`], e.g. to report on a test suite sharded across CI machines.
+    ///
+    /// The bodies are concatenated in the given order and the top-level tests renumbered sequentially, starting at
+    /// `1`. Tests within [`crate::Subtest`]s keep their numbers. The plans are summed up into a single `1..total` plan,
+    /// dropping their reasons. Should the preambles declare different TAP versions, the highest one is taken, and a
+    /// [`WarningCategory::VersionConflict`] is returned for each document declaring another one. With the `tracing`
+    /// feature enabled, the conflict is also reported as a warning event. Merging no documents at all yields an empty
+    /// TAP14 document planning `1..0`.
+    ///
+    /// Spans of the merged statements and of the warnings keep referring to their respective source, whereas the
+    /// merged document carries [`Span::default`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::{Document, WarningCategory};
+    ///
+    /// let shards = [
+    ///     "TAP version 13\n1..2\nok 1 - foo()\nok 2 - bar()\n",
+    ///     "TAP version 14\n1..1\nnot ok 1 - baz()\n",
+    /// ];
+    /// let docs = shards
+    ///     .iter()
+    ///     .map(|shard| Document::parse_from_str(shard))
+    ///     .collect::<Result<Vec<_>, _>>()
+    ///     .expect("Parser error");
+    /// let (merged, warnings) = Document::merge(docs);
+    /// assert_eq!(
+    ///     merged.to_string(),
+    ///     concat!(
+    ///         "TAP version 14\n",
+    ///         "1..3\n",
+    ///         "ok 1 - foo()\n",
+    ///         "ok 2 - bar()\n",
+    ///         "not ok 3 - baz()\n",
+    ///     )
+    /// );
+    /// assert_eq!(warnings.len(), 1);
+    /// assert_eq!(warnings[0].category, WarningCategory::VersionConflict);
+    /// assert_eq!(warnings[0].to_string(), "Line 1: Document declares TAP version 13, merged as version 14");
+    /// ```
+    pub fn merge(docs: Vec<Document>) -> (OwnedDocument, Vec<Warning>) {
+        let version = docs
+            .iter()
+            .map(|doc| &doc.preamble)
+            .max_by_key(|preamble| preamble.version_parts())
+            .map(|preamble| preamble.version);
+        let warnings: Vec<_> = docs
+            .iter()
+            .filter(|doc| Some(doc.preamble.version) != version)
+            .map(|doc| Warning {
+                category: WarningCategory::VersionConflict,
+                span: doc.span,
+                message: format!(
+                    "Document declares TAP version {}, merged as version {}",
+                    doc.preamble.version,
+                    version.unwrap_or_default()
+                ),
+            })
+            .collect();
+        #[cfg(feature = "tracing")]
+        if !warnings.is_empty() {
+            tracing::warn!(
+                target: "tap",
+                version,
+                "Merged documents declare different TAP versions, taking the highest"
+            );
+        }
+        let mut merged = OwnedDocument {
+            preamble: OwnedPreamble {
+                version: version.unwrap_or("14").into(),
+            },
+            plan: OwnedPlan::default(),
+            body: Vec::new(),
+            span: Span::default(),
+        };
+
+        let mut total = 0;
+        for doc in docs {
+            total += doc.plan.count();
+            merged.body.extend(doc.into_owned().body);
+        }
+        merged.plan.last = i32::try_from(total).unwrap_or(i32::MAX);
+
+        let mut number = 0;
+        for statement in &mut merged.body {
+            if let OwnedStatement::Test(test) = statement {
+                number += 1;
+                test.number = Some(number);
+                test.raw_number = None;
+                test.effective_number = number;
+            }
+        }
+        (merged, warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn test_merge_shards() {
+        let first = Document::parse_from_str(concat!(
+            "TAP version 14\n",
+            "1..2\n",
+            "ok 1 - a\n",
+            "# Subtest: b\n",
+            "    1..1\n",
+            "    ok 1 - b1\n",
+            "ok 2 - b\n",
+        ))
+        .expect("Parser error");
+        let second = Document::parse_from_str(concat!(
+            "TAP version 14.1\n",
+            "3..5 # shard 2\n",
+            "ok 003 - c\n",
+            "ok - d\n",
+            "not ok 5 - e\n",
+        ))
+        .expect("Parser error");
+        let skipped =
+            Document::parse_from_str("TAP version 13\n1..0 # no tests\n").expect("Parser error");
+
+        let (merged, warnings) = Document::merge(vec![first, second, skipped]);
+        assert_eq!(merged.preamble.version, "14.1");
+        let conflicts: Vec<_> = warnings.iter().map(|w| w.span.start).collect();
+        assert_eq!(conflicts, [0, 0]);
+        assert_eq!(
+            warnings[1].message,
+            "Document declares TAP version 13, merged as version 14.1"
+        );
+        assert_eq!((merged.plan.first, merged.plan.last), (1, 5));
+        assert_eq!(merged.plan.reason, None);
+        let doc = merged.as_borrowed();
+        let tests: Vec<_> = doc
+            .tests_with_depth()
+            .map(|(depth, t)| (depth, t.number, t.effective_number))
+            .collect();
+        assert_eq!(
+            tests,
+            [
+                (0, Some(1), 1),
+                (1, Some(1), 1),
+                (0, Some(2), 2),
+                (0, Some(3), 3),
+                (0, Some(4), 4),
+                (0, Some(5), 5),
+            ]
+        );
+        assert!(doc.validate_plan().is_ok());
+
+        let (empty, warnings) = Document::merge(Vec::new());
+        assert!(warnings.is_empty());
+        assert_eq!(
+            (empty.preamble.version.as_str(), empty.plan.last),
+            ("14", 0)
+        );
+    }
+}
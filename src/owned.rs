@@ -0,0 +1,794 @@
+//! Owned counterparts of the borrowed parser types.
+//!
+//! The types in the crate root borrow from the parsed input. Their `Owned*` mirrors hold [`String`]s instead, so they
+//! can outlive the input buffer. Use [`Document::into_owned`] (or the `into_owned` method of any other type) to convert.
+//!
+//! With the `serde` feature, the owned types also implement `Deserialize`, unlike their borrowed counterparts, so a
+//! serialized document can be reconstructed. Note that [`OwnedTest::raw_number`] and [`OwnedSubtest::raw_declaration`]
+//! are not serialized and thus always [`None`] after deserializing, and YAML blocks serialized through `YamlAsObject`
+//! can't be deserialized.
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    collections::BTreeMap,
+    string::String,
+    vec::Vec,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use std::{
+    convert::TryFrom,
+    io::{self, Read},
+};
+
+use crate::{
+    BailOut, Directive, Document, Key, Plan, Pragma, Preamble, Span, Statement, Subtest, Test,
+};
+#[cfg(feature = "std")]
+use crate::{Limit, ParseOptions, TapError};
+
+/// Owned version of [`Preamble`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OwnedPreamble {
+    /// TAP specification version. Can be any semantic version string (e.g. `14` or `14.1.3`).
+    pub version: String,
+}
+
+/// Owned version of [`Plan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OwnedPlan {
+    /// ID of first planned test, usually `1`, see [`Plan::first`].
+    pub first: i32,
+    /// ID of last planned test. A value of `0` _should_ indicate no tests were executed.
+    pub last: i32,
+    /// Arbitrary string which _should_ indicate why the certain tests were skipped.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "crate::serialize::is_omitted_none")
+    )]
+    pub reason: Option<String>,
+}
+
+/// Owned version of [`Pragma`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OwnedPragma {
+    /// If present, declares if the given `option` should be enabled or disabled.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "crate::serialize::is_omitted_none")
+    )]
+    pub flag: Option<bool>,
+    /// Pragma option identifier.
+    pub option: String,
+    /// Location of the pragma in the source.
+    pub span: Span,
+}
+
+/// Owned version of [`BailOut`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OwnedBailOut {
+    /// Optional reason for bailing out of the test procedure.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "crate::serialize::is_omitted_none")
+    )]
+    pub reason: Option<String>,
+    /// Location of the bail-out in the source.
+    pub span: Span,
+}
+
+/// Owned version of [`Key`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum OwnedKey {
+    /// Test was skipped
+    Skip,
+    /// Test has a to-do.
+    Todo,
+    /// A nonstandard directive keyword.
+    Other(String),
+}
+
+/// Owned version of [`Directive`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OwnedDirective {
+    /// A directive key, declaring the nature of this [`OwnedDirective`].
+    pub key: OwnedKey,
+    /// A reason why this test was [`Key::Skip`]ped or why it is a [`Key::Todo`], trimmed like [`Directive::reason`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "crate::serialize::is_omitted_none")
+    )]
+    pub reason: Option<String>,
+    /// The reason as written in the source, before trimming.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub raw_reason: Option<String>,
+}
+
+#[cfg(feature = "serde")]
+fn default_dash() -> bool {
+    true
+}
+
+/// Owned version of [`Test`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OwnedTest {
+    /// Result of the test.
+    pub result: bool,
+    /// Number of the test. Leading zeros are normalized away, e.g. `007` yields `7`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "crate::serialize::is_omitted_none")
+    )]
+    pub number: Option<i32>,
+    /// Number of the test exactly as written in the source, e.g. `007`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub raw_number: Option<String>,
+    /// Position of the test within its plan scope, see [`Test::effective_number`]. Defaults to `0` if missing from
+    /// the deserialized input.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub effective_number: i32,
+    /// Description of the test.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "crate::serialize::is_omitted_none")
+    )]
+    pub description: Option<String>,
+    /// Whether the description is separated by the canonical `-`, see [`Test::dash`]. Defaults to `true` if missing
+    /// from the deserialized input.
+    #[cfg_attr(feature = "serde", serde(default = "default_dash"))]
+    pub dash: bool,
+    /// Directive detailing this tests meta-execution.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "crate::serialize::is_omitted_none")
+    )]
+    pub directive: Option<OwnedDirective>,
+    /// Metadata attached by `# @key: value` comments directly preceding the test.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    pub metadata: BTreeMap<String, String>,
+    /// List of YAML lines detailing the test execution.
+    pub yaml: Vec<String>,
+    /// The YAML block exactly as written in the source, including its `---` and `...` markers.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub yaml_raw: Option<String>,
+    /// Location of the test in the source, including its YAML block.
+    pub span: Span,
+}
+
+/// Owned version of [`Subtest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OwnedSubtest {
+    /// Name of the subtest, declared by a comment at the start of the [`OwnedSubtest`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "crate::serialize::is_omitted_none")
+    )]
+    pub name: Option<String>,
+    /// The comment declaring the subtest exactly as written in the source, e.g. `# Subtest:foo`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub raw_declaration: Option<String>,
+    /// The [`OwnedPlan`] of the [`OwnedSubtest`].
+    pub plan: OwnedPlan,
+    /// Main body of the [`OwnedSubtest`].
+    pub body: Vec<OwnedStatement>,
+    /// Location of the subtest in the source, starting at its declaring comment if present.
+    pub span: Span,
+}
+
+/// Owned version of [`Statement`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum OwnedStatement {
+    /// Any text not captured by another [`OwnedStatement`] variant.
+    #[cfg_attr(feature = "serde", serde(rename = "anything"))]
+    Anything(String),
+    /// The text of a standalone comment line, without the leading `#`.
+    #[cfg_attr(feature = "serde", serde(rename = "comment"))]
+    Comment(String),
+    /// An [`OwnedBailOut`] statement.
+    #[cfg_attr(feature = "serde", serde(rename = "bail_out"))]
+    BailOut(OwnedBailOut),
+    /// An [`OwnedPragma`] statement.
+    #[cfg_attr(feature = "serde", serde(rename = "pragma"))]
+    Pragma(OwnedPragma),
+    /// An [`OwnedSubtest`] statement.
+    #[cfg_attr(feature = "serde", serde(rename = "subtest"))]
+    Subtest(OwnedSubtest),
+    /// An [`OwnedTest`] statement.
+    #[cfg_attr(feature = "serde", serde(rename = "test"))]
+    Test(OwnedTest),
+}
+
+/// Owned version of [`Document`]. Unlike [`Document`] it does not borrow from the parsed input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OwnedDocument {
+    /// The document's preamble.
+    pub preamble: OwnedPreamble,
+    /// The document's top-level plan declaration.
+    pub plan: OwnedPlan,
+    /// The document's top-level body as a collection of [`OwnedStatement`]s.
+    pub body: Vec<OwnedStatement>,
+    /// Location of the document in the source.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub span: Span,
+}
+
+impl From<&Preamble<'_>> for OwnedPreamble {
+    fn from(preamble: &Preamble<'_>) -> Self {
+        Self {
+            version: preamble.version.to_owned(),
+        }
+    }
+}
+
+impl From<&Plan<'_>> for OwnedPlan {
+    fn from(plan: &Plan<'_>) -> Self {
+        Self {
+            first: plan.first,
+            last: plan.last,
+            reason: plan.reason.map(str::to_owned),
+        }
+    }
+}
+
+impl From<&Pragma<'_>> for OwnedPragma {
+    fn from(pragma: &Pragma<'_>) -> Self {
+        Self {
+            flag: pragma.flag,
+            option: pragma.option.to_owned(),
+            span: pragma.span,
+        }
+    }
+}
+
+impl From<&BailOut<'_>> for OwnedBailOut {
+    fn from(bail_out: &BailOut<'_>) -> Self {
+        Self {
+            reason: bail_out.reason.map(str::to_owned),
+            span: bail_out.span,
+        }
+    }
+}
+
+impl From<&Key<'_>> for OwnedKey {
+    fn from(key: &Key<'_>) -> Self {
+        match key {
+            Key::Skip => Self::Skip,
+            Key::Todo => Self::Todo,
+            Key::Other(key) => Self::Other((*key).to_owned()),
+        }
+    }
+}
+
+impl From<&Directive<'_>> for OwnedDirective {
+    fn from(directive: &Directive<'_>) -> Self {
+        Self {
+            key: OwnedKey::from(&directive.key),
+            reason: directive.reason.map(str::to_owned),
+            raw_reason: directive.raw_reason.map(str::to_owned),
+        }
+    }
+}
+
+impl From<&Test<'_>> for OwnedTest {
+    fn from(test: &Test<'_>) -> Self {
+        Self {
+            result: test.result,
+            number: test.number,
+            raw_number: test.raw_number.map(str::to_owned),
+            effective_number: test.effective_number,
+            description: test.description.as_deref().map(str::to_owned),
+            dash: test.dash,
+            directive: test.directive.as_ref().map(OwnedDirective::from),
+            metadata: test
+                .metadata
+                .iter()
+                .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+                .collect(),
+            yaml: test.yaml.iter().copied().map(str::to_owned).collect(),
+            yaml_raw: test.yaml_raw.map(str::to_owned),
+            span: test.span,
+        }
+    }
+}
+
+impl From<&Subtest<'_>> for OwnedSubtest {
+    fn from(subtest: &Subtest<'_>) -> Self {
+        Self {
+            name: subtest.name.map(str::to_owned),
+            raw_declaration: subtest.raw_declaration.map(str::to_owned),
+            plan: OwnedPlan::from(&subtest.plan),
+            body: subtest.body.iter().map(OwnedStatement::from).collect(),
+            span: subtest.span,
+        }
+    }
+}
+
+impl From<&Statement<'_>> for OwnedStatement {
+    fn from(statement: &Statement<'_>) -> Self {
+        match statement {
+            Statement::Anything(a) => Self::Anything((*a).to_owned()),
+            Statement::Comment(c) => Self::Comment((*c).to_owned()),
+            Statement::BailOut(b) => Self::BailOut(b.into()),
+            Statement::Pragma(p) => Self::Pragma(p.into()),
+            Statement::Subtest(s) => Self::Subtest(s.into()),
+            Statement::Test(t) => Self::Test(t.into()),
+        }
+    }
+}
+
+impl From<&Document<'_>> for OwnedDocument {
+    fn from(document: &Document<'_>) -> Self {
+        Self {
+            preamble: OwnedPreamble::from(&document.preamble),
+            plan: OwnedPlan::from(&document.plan),
+            body: document.body.iter().map(OwnedStatement::from).collect(),
+            span: document.span,
+        }
+    }
+}
+
+impl<'a> From<&'a OwnedPreamble> for Preamble<'a> {
+    fn from(preamble: &'a OwnedPreamble) -> Self {
+        Self {
+            version: &preamble.version,
+        }
+    }
+}
+
+impl<'a> From<&'a OwnedPlan> for Plan<'a> {
+    fn from(plan: &'a OwnedPlan) -> Self {
+        Self {
+            first: plan.first,
+            last: plan.last,
+            reason: plan.reason.as_deref(),
+        }
+    }
+}
+
+impl<'a> From<&'a OwnedPragma> for Pragma<'a> {
+    fn from(pragma: &'a OwnedPragma) -> Self {
+        Self {
+            flag: pragma.flag,
+            option: &pragma.option,
+            span: pragma.span,
+        }
+    }
+}
+
+impl<'a> From<&'a OwnedBailOut> for BailOut<'a> {
+    fn from(bail_out: &'a OwnedBailOut) -> Self {
+        Self {
+            reason: bail_out.reason.as_deref(),
+            span: bail_out.span,
+        }
+    }
+}
+
+impl<'a> From<&'a OwnedKey> for Key<'a> {
+    fn from(key: &'a OwnedKey) -> Self {
+        match key {
+            OwnedKey::Skip => Self::Skip,
+            OwnedKey::Todo => Self::Todo,
+            OwnedKey::Other(key) => Self::Other(key),
+        }
+    }
+}
+
+impl<'a> From<&'a OwnedDirective> for Directive<'a> {
+    fn from(directive: &'a OwnedDirective) -> Self {
+        Self {
+            key: Key::from(&directive.key),
+            reason: directive.reason.as_deref(),
+            raw_reason: directive.raw_reason.as_deref(),
+        }
+    }
+}
+
+impl<'a> From<&'a OwnedTest> for Test<'a> {
+    fn from(test: &'a OwnedTest) -> Self {
+        Self {
+            result: test.result,
+            number: test.number,
+            raw_number: test.raw_number.as_deref(),
+            effective_number: test.effective_number,
+            description: test.description.as_deref().map(Cow::Borrowed),
+            dash: test.dash,
+            directive: test.directive.as_ref().map(Directive::from),
+            metadata: test
+                .metadata
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .collect(),
+            yaml: test.yaml.iter().map(String::as_str).collect(),
+            yaml_raw: test.yaml_raw.as_deref(),
+            span: test.span,
+        }
+    }
+}
+
+impl<'a> From<&'a OwnedSubtest> for Subtest<'a> {
+    fn from(subtest: &'a OwnedSubtest) -> Self {
+        Self {
+            name: subtest.name.as_deref(),
+            raw_declaration: subtest.raw_declaration.as_deref(),
+            plan: Plan::from(&subtest.plan),
+            body: subtest.body.iter().map(Statement::from).collect(),
+            span: subtest.span,
+        }
+    }
+}
+
+impl<'a> From<&'a OwnedStatement> for Statement<'a> {
+    fn from(statement: &'a OwnedStatement) -> Self {
+        match statement {
+            OwnedStatement::Anything(a) => Self::Anything(a),
+            OwnedStatement::Comment(c) => Self::Comment(c),
+            OwnedStatement::BailOut(b) => Self::BailOut(b.into()),
+            OwnedStatement::Pragma(p) => Self::Pragma(p.into()),
+            OwnedStatement::Subtest(s) => Self::Subtest(s.into()),
+            OwnedStatement::Test(t) => Self::Test(t.into()),
+        }
+    }
+}
+
+impl<'a> From<&'a OwnedDocument> for Document<'a> {
+    fn from(document: &'a OwnedDocument) -> Self {
+        Self {
+            preamble: Preamble::from(&document.preamble),
+            plan: Plan::from(&document.plan),
+            body: document.body.iter().map(Statement::from).collect(),
+            span: document.span,
+        }
+    }
+}
+
+impl Preamble<'_> {
+    /// Convert into an [`OwnedPreamble`].
+    pub fn into_owned(self) -> OwnedPreamble {
+        OwnedPreamble::from(&self)
+    }
+}
+
+impl Plan<'_> {
+    /// Convert into an [`OwnedPlan`].
+    pub fn into_owned(self) -> OwnedPlan {
+        OwnedPlan::from(&self)
+    }
+}
+
+impl Pragma<'_> {
+    /// Convert into an [`OwnedPragma`].
+    pub fn into_owned(self) -> OwnedPragma {
+        OwnedPragma::from(&self)
+    }
+}
+
+impl BailOut<'_> {
+    /// Convert into an [`OwnedBailOut`].
+    pub fn into_owned(self) -> OwnedBailOut {
+        OwnedBailOut::from(&self)
+    }
+}
+
+impl Directive<'_> {
+    /// Convert into an [`OwnedDirective`].
+    pub fn into_owned(self) -> OwnedDirective {
+        OwnedDirective::from(&self)
+    }
+}
+
+impl Test<'_> {
+    /// Convert into an [`OwnedTest`].
+    pub fn into_owned(self) -> OwnedTest {
+        OwnedTest::from(&self)
+    }
+}
+
+impl Subtest<'_> {
+    /// Convert into an [`OwnedSubtest`], recursively converting its body.
+    pub fn into_owned(self) -> OwnedSubtest {
+        OwnedSubtest::from(&self)
+    }
+
+    /// Promote this [`Subtest`] into a standalone [`OwnedDocument`], declaring the given TAP `version` in its
+    /// preamble. The subtest's plan and body become the document's top-level plan and body.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::{Document, Statement};
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..1\n",
+    ///     "# Subtest: flaky group\n",
+    ///     "    1..2\n",
+    ///     "    ok 1 - foo\n",
+    ///     "    not ok 2 - bar\n",
+    ///     "not ok 1 - flaky group\n",
+    /// );
+    /// let doc = Document::parse_from_str(content).expect("Parser error");
+    /// let Statement::Subtest(subtest) = &doc.body[0] else { panic!() };
+    ///
+    /// let extracted = subtest.to_document("14");
+    /// assert_eq!(extracted.preamble.version, "14");
+    /// assert_eq!(extracted.plan.last, 2);
+    /// assert_eq!(extracted.body.len(), 2);
+    /// ```
+    pub fn to_document(&self, version: &str) -> OwnedDocument {
+        OwnedDocument {
+            preamble: OwnedPreamble {
+                version: version.to_owned(),
+            },
+            plan: OwnedPlan::from(&self.plan),
+            body: self.body.iter().map(OwnedStatement::from).collect(),
+            span: self.span,
+        }
+    }
+}
+
+impl Statement<'_> {
+    /// Convert into an [`OwnedStatement`].
+    pub fn into_owned(self) -> OwnedStatement {
+        OwnedStatement::from(&self)
+    }
+}
+
+impl Document<'_> {
+    /// Convert into an [`OwnedDocument`], which no longer borrows from the parsed input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::{Document, OwnedDocument, OwnedStatement};
+    ///
+    /// let mut docs: Vec<OwnedDocument> = Vec::new();
+    /// for i in 1..=2 {
+    ///     let content = format!("TAP version 14\n1..1\nok 1 - run {}\n", i);
+    ///     let doc = Document::parse_from_str(&content).expect("Parser error");
+    ///     docs.push(doc.into_owned());
+    /// }
+    /// assert!(matches!(
+    ///     &docs[1].body[0],
+    ///     OwnedStatement::Test(t) if t.description.as_deref() == Some("run 2")
+    /// ));
+    /// ```
+    pub fn into_owned(self) -> OwnedDocument {
+        OwnedDocument::from(&self)
+    }
+}
+
+/// An empty plan, `1..0`, like [`Plan::default`].
+impl Default for OwnedPlan {
+    fn default() -> Self {
+        Self::new(1, 0)
+    }
+}
+
+impl OwnedPlan {
+    /// Create an [`OwnedPlan`] of the tests `first..last`, without a reason, like [`Plan::new`].
+    pub fn new(first: i32, last: i32) -> Self {
+        Self {
+            first,
+            last,
+            reason: None,
+        }
+    }
+}
+
+impl OwnedDocument {
+    /// Borrow as [`Document`], giving access to everything implemented on the borrowed types, e.g. [`Display`].
+    ///
+    /// [`Display`]: core::fmt::Display
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!("TAP version 14\n", "1..2\n", "ok 1\n", "not ok 2\n");
+    /// let owned = Document::parse_from_str(content).expect("Parser error").into_owned();
+    /// assert_eq!(owned.as_borrowed().summary().failed, 1);
+    /// ```
+    pub fn as_borrowed(&self) -> Document<'_> {
+        Document::from(self)
+    }
+
+    /// Read `reader` to its end and parse the content as [`OwnedDocument`].
+    ///
+    /// Parsing only commences after the whole input was read. Fails with [`crate::TapError::Io`] if reading fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::OwnedDocument;
+    ///
+    /// let content = concat!("TAP version 14\n", "1..1\n", "ok 1 - foo()\n");
+    /// let doc = OwnedDocument::parse_from_reader(content.as_bytes()).expect("Parser error");
+    /// assert_eq!(doc.body.len(), 1);
+    /// ```
+    ///
+    /// Reading from a file:
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use tapconsooomer::OwnedDocument;
+    ///
+    /// let file = File::open("results.tap").expect("Failed to open file");
+    /// let doc = OwnedDocument::parse_from_reader(file).expect("Parser error");
+    /// ```
+    ///
+    /// Only available with the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn parse_from_reader<R: Read>(reader: R) -> crate::Result<Self> {
+        Self::parse_from_reader_with_options(reader, &ParseOptions::default())
+    }
+
+    /// Read `reader` to its end and parse the content as [`OwnedDocument`], applying the given [`ParseOptions`].
+    ///
+    /// With [`ParseOptions::max_bytes`] set, reading stops right past the limit, failing with
+    /// [`crate::TapError::LimitExceeded`] instead of buffering an unbounded stream.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::{Limit, OwnedDocument, ParseOptions, TapError};
+    ///
+    /// let content = concat!("TAP version 14\n", "1..1\n", "ok 1 - foo()\n");
+    /// let options = ParseOptions::new().max_bytes(16);
+    /// let err = OwnedDocument::parse_from_reader_with_options(content.as_bytes(), &options).unwrap_err();
+    /// assert!(matches!(err, TapError::LimitExceeded { limit: Limit::Bytes, max: 16 }));
+    /// ```
+    ///
+    /// Only available with the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn parse_from_reader_with_options<R: Read>(
+        mut reader: R,
+        options: &ParseOptions,
+    ) -> crate::Result<Self> {
+        let mut bytes = Vec::new();
+        match options.max_bytes {
+            /* Reading a single byte past the limit suffices to tell it was exceeded. */
+            Some(max) => {
+                let limit = u64::try_from(max).unwrap_or(u64::MAX).saturating_add(1);
+                reader.take(limit).read_to_end(&mut bytes)?;
+                if bytes.len() > max {
+                    return Err(TapError::LimitExceeded {
+                        limit: Limit::Bytes,
+                        max,
+                    });
+                }
+            }
+            None => {
+                reader.read_to_end(&mut bytes)?;
+            }
+        }
+        let content =
+            String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(Document::parse_from_str_with_options(&content, options)?.into_owned())
+    }
+
+    /// Rename the [`OwnedSubtest`] located by `path`, returning whether it was found.
+    ///
+    /// `path` lists the names of the enclosing subtests from the top-level body downwards, ending with the name of the
+    /// subtest to rename. If several subtests share a name, the first one is picked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::{Document, OwnedStatement};
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..1\n",
+    ///     "# Subtest: /tmp/build-1234/outer\n",
+    ///     "    # Subtest: inner\n",
+    ///     "        1..1\n",
+    ///     "        ok 1\n",
+    ///     "    1..1\n",
+    ///     "ok 1\n",
+    /// );
+    /// let mut doc = Document::parse_from_str(content).expect("Parser error").into_owned();
+    /// assert!(doc.rename_subtest(&["/tmp/build-1234/outer"], "outer"));
+    /// assert!(doc.rename_subtest(&["outer", "inner"], "renamed"));
+    /// assert!(!doc.rename_subtest(&["missing"], "whatever"));
+    ///
+    /// let OwnedStatement::Subtest(outer) = &doc.body[0] else { panic!() };
+    /// let OwnedStatement::Subtest(inner) = &outer.body[0] else { panic!() };
+    /// assert_eq!(outer.name.as_deref(), Some("outer"));
+    /// assert_eq!(inner.name.as_deref(), Some("renamed"));
+    /// ```
+    pub fn rename_subtest(&mut self, path: &[&str], new_name: &str) -> bool {
+        fn find<'b>(body: &'b mut [OwnedStatement], path: &[&str]) -> Option<&'b mut OwnedSubtest> {
+            let (first, rest) = path.split_first()?;
+            let subtest = body.iter_mut().find_map(|s| match s {
+                OwnedStatement::Subtest(s) if s.name.as_deref() == Some(*first) => Some(s),
+                _ => None,
+            })?;
+            if rest.is_empty() {
+                Some(subtest)
+            } else {
+                find(&mut subtest.body, rest)
+            }
+        }
+
+        match find(&mut self.body, path) {
+            Some(subtest) => {
+                subtest.name = Some(new_name.to_owned());
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_deserialize_round_trip() {
+        use alloc::format;
+        use std::fs;
+
+        for example in [
+            "cascading",
+            "common",
+            "metadata",
+            "small",
+            "yaml",
+            "move_mount_set_group_test",
+        ] {
+            let contents = fs::read_to_string(format!("examples/{}.tap", example))
+                .expect("Failed to read file");
+            let options = crate::ParseOptions::new().comment_metadata(true);
+            let doc =
+                Document::parse_from_str_with_options(&contents, &options).expect("Parser error");
+            let json = serde_json::to_string(&doc).unwrap();
+            let owned: OwnedDocument = serde_json::from_str(&json).expect("Deserialize error");
+            assert_eq!(serde_json::to_string(&owned).unwrap(), json, "{}", example);
+        }
+    }
+
+    #[test]
+    fn test_parse_from_reader_invalid_utf8() {
+        let err = OwnedDocument::parse_from_reader(&b"TAP version 14\n1..1\nok 1 - \xff\n"[..])
+            .unwrap_err();
+        assert!(matches!(err, crate::TapError::Io(_)));
+    }
+
+    #[test]
+    fn test_parse_from_reader_stops_at_max_bytes() {
+        let endless = io::repeat(b'#');
+        let options = ParseOptions::new().max_bytes(1024);
+        let err = OwnedDocument::parse_from_reader_with_options(endless, &options).unwrap_err();
+        assert!(matches!(
+            err,
+            TapError::LimitExceeded {
+                limit: Limit::Bytes,
+                max: 1024
+            }
+        ));
+
+        let content = "TAP version 14\n1..1\nok 1\n";
+        let options = ParseOptions::new().max_bytes(content.len());
+        let doc = OwnedDocument::parse_from_reader_with_options(content.as_bytes(), &options)
+            .expect("Parser error");
+        assert_eq!(doc.body.len(), 1);
+    }
+}
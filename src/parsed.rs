@@ -0,0 +1,113 @@
+//! A parsed document bundled with its source.
+
+use alloc::string::String;
+use core::fmt;
+
+use ouroboros::self_referencing;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer};
+
+use crate::{Document, ParseOptions, Result};
+
+#[self_referencing]
+struct Inner {
+    source: String,
+    #[borrows(source)]
+    #[covariant]
+    document: Document<'this>,
+}
+
+/// A [`Document`] together with the [`String`] it borrows from.
+///
+/// Parsing borrows from the input, so the input has to be kept alive alongside the resulting [`Document`]. This is
+/// awkward whenever a document has to be returned from a function or stored in a struct. [`ParsedDocument`] takes
+/// ownership of the input instead, without the cost of converting every statement through
+/// [`Document::into_owned`].
+///
+/// # Examples
+///
+/// ```
+/// use tapconsooomer::ParsedDocument;
+///
+/// fn load() -> ParsedDocument {
+///     let content = String::from("TAP version 14\n1..1\nok 1 - foo()\n");
+///     ParsedDocument::parse(content).expect("Parser error")
+/// }
+///
+/// let parsed = load();
+/// assert_eq!(parsed.document().plan.last, 1);
+/// assert!(parsed.source().starts_with("TAP version 14"));
+/// ```
+pub struct ParsedDocument(Inner);
+
+impl ParsedDocument {
+    /// Parse `source` into a [`Document`], taking ownership of it.
+    pub fn parse(source: String) -> Result<Self> {
+        Self::parse_with_options(source, &ParseOptions::default())
+    }
+
+    /// Parse `source` into a [`Document`] using custom [`ParseOptions`], taking ownership of it.
+    pub fn parse_with_options(source: String, options: &ParseOptions) -> Result<Self> {
+        InnerTryBuilder {
+            source,
+            document_builder: |source: &String| {
+                Document::parse_from_str_with_options(source, options)
+            },
+        }
+        .try_build()
+        .map(Self)
+    }
+
+    /// The parsed document.
+    pub fn document(&self) -> &Document<'_> {
+        self.0.borrow_document()
+    }
+
+    /// The source the document was parsed from.
+    pub fn source(&self) -> &str {
+        self.0.borrow_source()
+    }
+
+    /// Drop the document, returning the source.
+    pub fn into_source(self) -> String {
+        self.0.into_heads().source
+    }
+}
+
+impl fmt::Debug for ParsedDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParsedDocument")
+            .field("document", self.document())
+            .finish()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for ParsedDocument {
+    fn serialize<S: Serializer>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> {
+        self.document().serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Statement;
+
+    #[test]
+    fn test_parsed_document() {
+        let content = String::from("TAP version 14\n1..2\nok 1 - foo()\nnot ok 2 - bar()\n");
+        let parsed = ParsedDocument::parse(content.clone()).expect("Parser error");
+        assert_eq!(
+            parsed.document(),
+            &Document::parse_from_str(&content).expect("Parser error")
+        );
+        assert!(matches!(
+            &parsed.document().body[1],
+            Statement::Test(t) if t.description == Some("bar()".into())
+        ));
+        assert_eq!(parsed.into_source(), content);
+
+        assert!(ParsedDocument::parse(String::from("1..1\nok\n")).is_err());
+    }
+}
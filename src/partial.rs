@@ -0,0 +1,179 @@
+//! Parsing of single grammar fragments, e.g. for linters and editor plugins working on parts of a line.
+
+use alloc::borrow::{Cow, ToOwned};
+
+use pest::{
+    error::{Error, ErrorVariant},
+    iterators::Pair,
+    Parser, Position,
+};
+
+use crate::{is_subtest_decl, trim_trailing, unescape_hash, Result, Rule, TAPParser, TapError};
+
+/// The kind of a single line of TAP, as determined by [`classify_line`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LineKind {
+    /// A `TAP version` preamble, see [`crate::Preamble`].
+    Preamble,
+    /// A plan like `1..4`, see [`crate::Plan`].
+    Plan,
+    /// A test line like `ok 1 - foo()`, see [`crate::Test`].
+    Test,
+    /// A `Bail out!` line, see [`crate::BailOut`].
+    BailOut,
+    /// A `pragma` line, see [`crate::Pragma`].
+    Pragma,
+    /// A `# Subtest` declaration, starting a [`crate::Subtest`].
+    Subtest,
+    /// Any other line starting with `#`.
+    Comment,
+    /// A line holding nothing but whitespace.
+    Empty,
+    /// Anything else, e.g. non-TAP output of the producer, or a line of a YAML block.
+    Other,
+}
+
+/// Parse `content` as `rule`, requiring the whole of `content` to match.
+fn parse_exact(rule: Rule, content: &str) -> Result<Pair<'_, Rule>> {
+    let pair = TAPParser::parse(rule, content)?
+        .next()
+        .ok_or(TapError::Empty)?;
+    let end = pair.as_span().end();
+    if end < content.len() {
+        return Err(Error::new_from_pos(
+            ErrorVariant::CustomError {
+                message: "unexpected trailing input".to_owned(),
+            },
+            Position::new(content, end).unwrap(),
+        )
+        .into());
+    }
+    Ok(pair)
+}
+
+/// Parse a test description, as found between the test number and any directive, e.g. `foo()` of
+/// `ok 1 - foo() # SKIP`.
+///
+/// Escaped hashes (`\#`) are unescaped and trailing whitespace is trimmed, like in [`crate::Test::description`]. The
+/// separating `-` is not part of the description, and neither is an unescaped `#`, as it would start a directive or
+/// comment.
+///
+/// # Examples
+///
+/// ```
+/// use tapconsooomer::parse_description;
+///
+/// assert_eq!(parse_description("foo() \\# 1").expect("Parser error"), "foo() # 1");
+/// assert_eq!(parse_description("foo \t ").expect("Parser error"), "foo");
+/// assert!(parse_description("foo() # SKIP").is_err());
+/// ```
+pub fn parse_description(content: &str) -> Result<Cow<'_, str>> {
+    let content = trim_trailing(content).unwrap_or(content);
+    parse_exact(Rule::description, content).map(|pair| unescape_hash(pair.as_str()))
+}
+
+/// Parse a test number, e.g. the `42` of `ok 42`. Leading zeros are allowed, like in [`crate::Test::number`].
+///
+/// # Examples
+///
+/// ```
+/// use tapconsooomer::{parse_number, TapError};
+///
+/// assert_eq!(parse_number("007").expect("Parser error"), 7);
+/// assert!(matches!(parse_number("-1"), Err(TapError::Grammar(_))));
+/// assert!(matches!(parse_number("99999999999"), Err(TapError::NumberParse(_))));
+/// ```
+pub fn parse_number(content: &str) -> Result<i32> {
+    parse_exact(Rule::number, content)?
+        .as_str()
+        .parse()
+        .map_err(TapError::NumberParse)
+}
+
+/// Classify a single line of TAP without assembling a document, e.g. for syntax highlighting or quickly filtering a
+/// log.
+///
+/// The line is matched against the grammar's rules for the individual statements, so it is classified like the
+/// document parser would, as long as it is not part of a YAML block, which takes knowing the preceding lines. Leading
+/// indentation, e.g. of a [`crate::Subtest`], as well as a trailing line terminator are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use tapconsooomer::{classify_line, LineKind};
+///
+/// assert_eq!(classify_line("TAP version 14\n"), LineKind::Preamble);
+/// assert_eq!(classify_line("1..4 # two skipped"), LineKind::Plan);
+/// assert_eq!(classify_line("    not ok 2 - bar() # TODO"), LineKind::Test);
+/// assert_eq!(classify_line("# Subtest: baz"), LineKind::Subtest);
+/// assert_eq!(classify_line("# Connecting to localhost"), LineKind::Comment);
+/// assert_eq!(classify_line("Compiling 3 files..."), LineKind::Other);
+/// ```
+pub fn classify_line(line: &str) -> LineKind {
+    const RULES: [(Rule, LineKind); 6] = [
+        (Rule::preamble, LineKind::Preamble),
+        (Rule::plan, LineKind::Plan),
+        (Rule::test, LineKind::Test),
+        (Rule::bail_out, LineKind::BailOut),
+        (Rule::pragma, LineKind::Pragma),
+        (Rule::comment, LineKind::Comment),
+    ];
+
+    let line = line.trim_matches([' ', '\t', '\r', '\n']);
+    if line.is_empty() {
+        LineKind::Empty
+    } else if is_subtest_decl(line) {
+        LineKind::Subtest
+    } else {
+        RULES
+            .iter()
+            .find(|(rule, _)| matches_exactly(*rule, line))
+            .map_or(LineKind::Other, |(_, kind)| *kind)
+    }
+}
+
+/// Whether `rule` matches the whole of `content`. Unlike [`parse_exact`], no error is built on mismatch.
+fn matches_exactly(rule: Rule, content: &str) -> bool {
+    TAPParser::parse(rule, content)
+        .ok()
+        .and_then(|mut pairs| pairs.next())
+        .is_some_and(|pair| pair.as_span().end() == content.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trailing_input() {
+        let err = parse_number("42 - foo").unwrap_err();
+        assert!(
+            matches!(&err, TapError::Grammar(e) if e.location == pest::error::InputLocation::Pos(2)),
+            "{:?}",
+            err
+        );
+        assert!(parse_description("").is_err());
+    }
+
+    #[test]
+    fn test_classify_line() {
+        let lines = [
+            ("tap version 13", LineKind::Preamble),
+            ("1..0 # SKIP no network\r\n", LineKind::Plan),
+            ("ok", LineKind::Test),
+            ("\tok 3 # SKIP", LineKind::Test),
+            ("Bail out! boom", LineKind::BailOut),
+            ("pragma +strict", LineKind::Pragma),
+            ("#subtest", LineKind::Subtest),
+            ("# Subtests follow", LineKind::Comment),
+            ("#", LineKind::Comment),
+            (" \t\n", LineKind::Empty),
+            ("  ---", LineKind::Other),
+            ("1..2 and more", LineKind::Other),
+        ];
+        for (line, kind) in lines {
+            assert_eq!(classify_line(line), kind, "{:?}", line);
+        }
+    }
+}
@@ -0,0 +1,547 @@
+//! Alternative serialization modes.
+
+use alloc::{string::String, vec::Vec};
+use core::cell::Cell;
+use std::thread::LocalKey;
+
+use serde::{
+    ser::{
+        SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+        SerializeTupleStruct, SerializeTupleVariant,
+    },
+    Serialize, Serializer,
+};
+use serde_yaml::Value;
+
+use crate::{parse_yaml, Document};
+
+std::thread_local! {
+    /// Set while an [`OmitNull`] is being serialized.
+    static OMIT_NULL: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Restores the previous state of a mode flag when dropped, even if serialization panics.
+struct Restore(&'static LocalKey<Cell<bool>>, bool);
+
+impl Restore {
+    /// Enable the mode `flag` until the returned [`Restore`] is dropped.
+    fn enable(flag: &'static LocalKey<Cell<bool>>) -> Self {
+        Self(flag, flag.with(|flag| flag.replace(true)))
+    }
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        self.0.with(|flag| flag.set(self.1));
+    }
+}
+
+/// Serializes the wrapped value with each YAML block emitted as a structured object rather than a list of raw lines.
+///
+/// Blocks that are not valid YAML, as well as tests without a YAML block, fall back to the list of raw lines. Works
+/// with any value containing [`crate::Test`]s or [`crate::OwnedTest`]s. Combined with [`OmitNull`], null values inside
+/// the blocks are left out as well, e.g. for formats such as TOML that can't represent them.
+///
+/// # Examples
+///
+/// ```
+/// use tapconsooomer::{Document, YamlAsObject};
+///
+/// let content = concat!(
+///     "TAP version 14\n",
+///     "1..1\n",
+///     "not ok 1 - foo()\n",
+///     "  ---\n",
+///     "  at:\n",
+///     "    line: 142\n",
+///     "  ...\n",
+/// );
+/// let doc = Document::parse_from_str(content).expect("Parser error");
+///
+/// let json = serde_json::to_value(&doc).unwrap();
+/// assert_eq!(json["body"][0]["test"]["yaml"][1], "  line: 142");
+///
+/// let json = serde_json::to_value(YamlAsObject(&doc)).unwrap();
+/// assert_eq!(json["body"][0]["test"]["yaml"]["at"]["line"], 142);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct YamlAsObject<'t, T: ?Sized>(pub &'t T);
+
+impl<T: Serialize + ?Sized> Serialize for YamlAsObject<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(YamlSerializer(serializer))
+    }
+}
+
+/// Forwards everything to the wrapped [`Serializer`], except for the `yaml` field of tests, which is swapped for the
+/// parsed YAML block, see [`YamlAsObject`].
+struct YamlSerializer<S>(S);
+
+/// Serializes the wrapped value through a [`YamlSerializer`], so the values nested inside a compound are covered too.
+struct Nested<'t, T: ?Sized>(&'t T);
+
+impl<T: Serialize + ?Sized> Serialize for Nested<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(YamlSerializer(serializer))
+    }
+}
+
+/// A compound value serialized through a [`YamlSerializer`]. `test` is set for the fields of a test.
+struct Compound<C> {
+    inner: C,
+    test: bool,
+}
+
+impl<C> Compound<C> {
+    fn new(inner: C) -> Self {
+        Self { inner, test: false }
+    }
+}
+
+/// The YAML block held by the `yaml` field of a test, or [`None`] if there is none or it isn't valid YAML. Null values
+/// are left out while an [`OmitNull`] is being serialized.
+fn yaml_object<T: Serialize + ?Sized>(lines: &T) -> Option<Value> {
+    let lines: Vec<String> = serde_json::to_value(lines)
+        .and_then(serde_json::from_value)
+        .ok()?;
+    if lines.is_empty() {
+        return None;
+    }
+    let mut value = parse_yaml(&lines).ok()?;
+    if OMIT_NULL.with(Cell::get) {
+        remove_nulls(&mut value);
+    }
+    Some(value)
+}
+
+/// Remove the null values from the mappings and sequences nested in `value`.
+fn remove_nulls(value: &mut Value) {
+    match value {
+        Value::Sequence(sequence) => {
+            sequence.retain(|v| !v.is_null());
+            sequence.iter_mut().for_each(remove_nulls);
+        }
+        Value::Mapping(mapping) => {
+            mapping.retain(|_, v| !v.is_null());
+            mapping.values_mut().for_each(remove_nulls);
+        }
+        Value::Tagged(tagged) => remove_nulls(&mut tagged.value),
+        _ => {}
+    }
+}
+
+macro_rules! forward {
+    ($($method:ident($type:ty)),* $(,)?) => {
+        $(
+            fn $method(self, v: $type) -> Result<S::Ok, S::Error> {
+                self.0.$method(v)
+            }
+        )*
+    };
+}
+
+impl<S: Serializer> Serializer for YamlSerializer<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = Compound<S::SerializeSeq>;
+    type SerializeTuple = Compound<S::SerializeTuple>;
+    type SerializeTupleStruct = Compound<S::SerializeTupleStruct>;
+    type SerializeTupleVariant = Compound<S::SerializeTupleVariant>;
+    type SerializeMap = Compound<S::SerializeMap>;
+    type SerializeStruct = Compound<S::SerializeStruct>;
+    type SerializeStructVariant = Compound<S::SerializeStructVariant>;
+
+    forward!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+        serialize_unit_struct(&'static str),
+    );
+
+    fn serialize_none(self) -> Result<S::Ok, S::Error> {
+        self.0.serialize_none()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<S::Ok, S::Error> {
+        self.0.serialize_some(&Nested(value))
+    }
+
+    fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+        self.0.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+    ) -> Result<S::Ok, S::Error> {
+        self.0.serialize_unit_variant(name, index, variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        self.0.serialize_newtype_struct(name, &Nested(value))
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        self.0
+            .serialize_newtype_variant(name, index, variant, &Nested(value))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+        self.0.serialize_seq(len).map(Compound::new)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, S::Error> {
+        self.0.serialize_tuple(len).map(Compound::new)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, S::Error> {
+        self.0.serialize_tuple_struct(name, len).map(Compound::new)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, S::Error> {
+        self.0
+            .serialize_tuple_variant(name, index, variant, len)
+            .map(Compound::new)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+        self.0.serialize_map(len).map(Compound::new)
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, S::Error> {
+        let test = matches!(name, "Test" | "OwnedTest");
+        self.0
+            .serialize_struct(name, len)
+            .map(|inner| Compound { inner, test })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        self.0
+            .serialize_struct_variant(name, index, variant, len)
+            .map(Compound::new)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.0.is_human_readable()
+    }
+}
+
+impl<C: SerializeSeq> SerializeSeq for Compound<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.inner.serialize_element(&Nested(value))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeTuple> SerializeTuple for Compound<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.inner.serialize_element(&Nested(value))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeTupleStruct> SerializeTupleStruct for Compound<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.inner.serialize_field(&Nested(value))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeTupleVariant> SerializeTupleVariant for Compound<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.inner.serialize_field(&Nested(value))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeMap> SerializeMap for Compound<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), C::Error> {
+        self.inner.serialize_key(&Nested(key))
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.inner.serialize_value(&Nested(value))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeStruct> SerializeStruct for Compound<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), C::Error> {
+        if self.test && key == "yaml" {
+            if let Some(yaml) = yaml_object(value) {
+                return self.inner.serialize_field(key, &yaml);
+            }
+        }
+        self.inner.serialize_field(key, &Nested(value))
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+        self.inner.skip_field(key)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeStructVariant> SerializeStructVariant for Compound<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), C::Error> {
+        self.inner.serialize_field(key, &Nested(value))
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+        self.inner.skip_field(key)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+/// Serializes the wrapped value leaving out absent optional fields, such as the number or description of a test,
+/// instead of emitting them as `null`. Makes the output considerably smaller for large documents.
+///
+/// Works with any value containing the parsed types or their `Owned*` counterparts, and combines with
+/// [`YamlAsObject`]. Deserializing the owned types treats missing fields as absent, so the output still round-trips.
+///
+/// # Examples
+///
+/// ```
+/// use tapconsooomer::{Document, OmitNull};
+///
+/// let doc = Document::parse_from_str("TAP version 14\n1..1\nok\n").expect("Parser error");
+///
+/// let json = serde_json::to_value(&doc).unwrap();
+/// assert!(json["body"][0]["test"]["number"].is_null());
+/// assert!(json["body"][0]["test"].as_object().unwrap().contains_key("description"));
+///
+/// let json = serde_json::to_value(OmitNull(&doc)).unwrap();
+/// assert!(!json["body"][0]["test"].as_object().unwrap().contains_key("description"));
+/// assert!(!json["plan"].as_object().unwrap().contains_key("reason"));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct OmitNull<'t, T: ?Sized>(pub &'t T);
+
+impl<T: Serialize + ?Sized> Serialize for OmitNull<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let _restore = Restore::enable(&OMIT_NULL);
+        self.0.serialize(serializer)
+    }
+}
+
+/// Parse `content` with [`Document::parse_from_str`] and serialize the result to compact JSON.
+///
+/// A convenience for embedding the parser, e.g. in a browser through WebAssembly. The library performs no I/O, so it
+/// builds for targets without a filesystem or stdin, such as `wasm32-unknown-unknown`.
+///
+/// # Examples
+///
+/// ```
+/// let json = tapconsooomer::parse_to_json("TAP version 14\n1..1\nok 1\n").expect("Parser error");
+/// assert!(json.starts_with(r#"{"preamble":{"version":"14"},"plan":{"first":1,"last":1"#));
+/// ```
+pub fn parse_to_json(content: &str) -> crate::Result<String> {
+    let document = Document::parse_from_str(content)?;
+    Ok(serde_json::to_string(&document)?)
+}
+
+/// Whether to leave out the optional field holding `value`, honoring [`OmitNull`].
+pub(crate) fn is_omitted_none<T>(value: &Option<T>) -> bool {
+    value.is_none() && OMIT_NULL.with(Cell::get)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Document, OwnedDocument};
+
+    #[test]
+    fn test_yaml_as_object_fallback() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..2\n",
+            "not ok 1\n",
+            "  ---\n",
+            "  message: [unclosed\n",
+            "  ...\n",
+            "ok 2\n",
+        );
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        let json = serde_json::to_value(YamlAsObject(&doc)).unwrap();
+        assert_eq!(json["body"][0]["test"]["yaml"][0], "message: [unclosed");
+        assert_eq!(json["body"][1]["test"]["yaml"], serde_json::json!([]));
+
+        let owned = serde_json::to_value(YamlAsObject(&doc.into_owned())).unwrap();
+        assert_eq!(owned, json);
+    }
+
+    #[test]
+    fn test_yaml_as_object_nested() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..1\n",
+            "# Subtest: sub\n",
+            "    1..1\n",
+            "    not ok 1\n",
+            "      ---\n",
+            "      severity: fail\n",
+            "      ...\n",
+            "not ok 1 - sub\n",
+        );
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        let json = serde_json::to_value(YamlAsObject(&doc)).unwrap();
+        assert_eq!(
+            json["body"][0]["subtest"]["body"][0]["test"]["yaml"]["severity"],
+            "fail"
+        );
+        /* The mode only applies while the wrapper is serialized. */
+        let json = serde_json::to_value(&doc).unwrap();
+        assert!(json["body"][0]["subtest"]["body"][0]["test"]["yaml"].is_array());
+    }
+
+    #[test]
+    fn test_yaml_as_object_omit_null() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..1\n",
+            "not ok 1\n",
+            "  ---\n",
+            "  found:\n",
+            "    hostname: example.com\n",
+            "    address: ~\n",
+            "  tries: [1, ~, 3]\n",
+            "  ...\n",
+        );
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        let json = serde_json::to_value(YamlAsObject(&doc)).unwrap();
+        assert!(json["body"][0]["test"]["yaml"]["found"]["address"].is_null());
+
+        let json = serde_json::to_value(OmitNull(&YamlAsObject(&doc))).unwrap();
+        let yaml = &json["body"][0]["test"]["yaml"];
+        assert_eq!(
+            yaml["found"],
+            serde_json::json!({ "hostname": "example.com" })
+        );
+        assert_eq!(yaml["tries"], serde_json::json!([1, 3]));
+        assert_eq!(
+            serde_json::to_value(YamlAsObject(&OmitNull(&doc))).unwrap(),
+            json
+        );
+        assert!(toml::to_string(&OmitNull(&YamlAsObject(&doc))).is_ok());
+    }
+
+    #[test]
+    fn test_omit_null_round_trip() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..2\n",
+            "# Subtest\n",
+            "    1..1\n",
+            "    ok # SKIP\n",
+            "ok 1\n",
+            "pragma strict\n",
+            "Bail out!\n",
+        );
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        let json = serde_json::to_string(&OmitNull(&YamlAsObject(&doc))).unwrap();
+        assert!(!json.contains("null"), "{}", json);
+
+        let owned: OwnedDocument = serde_json::from_str(&json).unwrap();
+        let verbose = serde_json::to_string(&YamlAsObject(&doc)).unwrap();
+        assert_eq!(
+            owned,
+            serde_json::from_str::<OwnedDocument>(&verbose).unwrap()
+        );
+        /* The mode only applies while the wrapper is serialized. */
+        assert!(serde_json::to_string(&owned).unwrap().contains("null"));
+    }
+}
@@ -0,0 +1,408 @@
+//! Incremental, line-oriented parsing of TAP streams.
+
+use alloc::{borrow::ToOwned, boxed::Box, string::String, vec, vec::Vec};
+
+use crate::{
+    is_subtest_decl, OwnedPlan, OwnedPreamble, OwnedStatement, Plan, Preamble, Result, Statement,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// No construct is pending.
+    #[default]
+    Idle,
+    /// A test line was seen, but a YAML block may still follow.
+    Test,
+    /// Inside the YAML block of a test. Holds the length of the block's anchor indentation.
+    Yaml(usize),
+    /// Inside a subtest, which ends at the next line that isn't indented.
+    Subtest,
+}
+
+/// An event yielded by [`StreamingParser::push_line_events`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParserEvent {
+    /// A completed top-level statement, as yielded by [`StreamingParser::push_line`]. Never holds a
+    /// [`OwnedStatement::BailOut`], see [`ParserEvent::BailOut`].
+    Statement(Box<OwnedStatement>),
+    /// The producer bailed out at the top level, holding the reason if any. No more tests are to be expected, all
+    /// further lines are reported as [`ParserEvent::Trailing`].
+    BailOut(Option<String>),
+    /// A line pushed after the bail-out, verbatim without its line terminator.
+    Trailing(String),
+}
+
+/// A parser consuming TAP input one line at a time, yielding each [`OwnedStatement`] as soon as it is complete.
+///
+/// Unlike [`crate::Document::parse_from_str`], no complete document is required. This makes it suitable for watching
+/// long-running or never-ending test suites. Since the pushed lines don't outlive the call, yielded statements are
+/// owned.
+///
+/// Some constructs can only be recognized as complete once the following line arrives:
+///
+/// - A test is held back until the next line shows whether a YAML block follows. If it does, the test is yielded once
+///   the block's `...` terminator arrives.
+/// - A subtest is held back until the next line that is not indented.
+///
+/// Call [`StreamingParser::finish`] at the end of input to flush any pending construct. Comments and empty lines
+/// outside of subtests and YAML blocks are skipped. The preamble and plan are not yielded as statements, but are made
+/// available through [`StreamingParser::preamble`] and [`StreamingParser::plan`].
+///
+/// Spans of yielded statements refer to the whole stream, assuming each pushed line was terminated by a single `\n`.
+///
+/// # Examples
+///
+/// ```
+/// use tapconsooomer::{OwnedStatement, StreamingParser};
+///
+/// let mut parser = StreamingParser::new();
+/// assert!(parser.push_line("TAP version 14").unwrap().is_empty());
+/// assert!(parser.push_line("1..2").unwrap().is_empty());
+/// assert!(parser.push_line("not ok 1 - foo").unwrap().is_empty());
+/// assert!(parser.push_line("  ---").unwrap().is_empty());
+/// assert!(parser.push_line("  message: boom").unwrap().is_empty());
+///
+/// let statements = parser.push_line("  ...").unwrap();
+/// assert!(matches!(&statements[..], [OwnedStatement::Test(t)] if t.yaml == ["message: boom"]));
+///
+/// assert!(parser.push_line("ok 2 - bar").unwrap().is_empty());
+/// let statements = parser.finish().unwrap();
+/// assert!(matches!(&statements[..], [OwnedStatement::Test(t)] if t.number == Some(2)));
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct StreamingParser {
+    state: State,
+    /// Lines of the pending construct, each terminated by `\n`.
+    buffer: String,
+    /// Byte offset of the pending construct within the stream.
+    buffer_start: usize,
+    /// Line number (1-based) of the pending construct within the stream.
+    buffer_line: usize,
+    /// Total bytes pushed so far.
+    offset: usize,
+    /// Total lines pushed so far.
+    lines: usize,
+    preamble: Option<OwnedPreamble>,
+    plan: Option<OwnedPlan>,
+    /// Effective number of the last top-level test.
+    number: i32,
+    /// Whether [`ParserEvent::BailOut`] was yielded.
+    bailed_out: bool,
+}
+
+impl StreamingParser {
+    /// Create a new [`StreamingParser`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The preamble, once it has been seen.
+    pub fn preamble(&self) -> Option<&OwnedPreamble> {
+        self.preamble.as_ref()
+    }
+
+    /// The top-level plan, once it has been seen.
+    pub fn plan(&self) -> Option<&OwnedPlan> {
+        self.plan.as_ref()
+    }
+
+    /// Push a single line of input, with or without its line terminator. Returns all [`OwnedStatement`]s completed by
+    /// this line.
+    pub fn push_line(&mut self, line: &str) -> Result<Vec<OwnedStatement>> {
+        let line = line
+            .strip_suffix('\n')
+            .map(|l| l.strip_suffix('\r').unwrap_or(l))
+            .unwrap_or(line);
+        self.lines += 1;
+
+        let mut completed = Vec::new();
+        let indented = line.starts_with([' ', '\t']);
+        let blank = line.trim().is_empty();
+
+        match self.state {
+            State::Yaml(indent) => {
+                self.append(line);
+                if line
+                    .get(indent..)
+                    .is_some_and(|rest| rest.trim_end() == "...")
+                {
+                    completed.push(self.flush()?);
+                }
+            }
+            State::Test if line.trim() == "---" && indented => {
+                self.append(line);
+                self.state = State::Yaml(line.len() - line.trim_start().len());
+            }
+            State::Subtest if indented || blank => self.append(line),
+            State::Test | State::Subtest => {
+                completed.push(self.flush()?);
+                completed.extend(self.start(line)?);
+            }
+            State::Idle => completed.extend(self.start(line)?),
+        }
+
+        self.offset += line.len() + 1;
+        Ok(completed)
+    }
+
+    /// Push a single line of input like [`StreamingParser::push_line`], but yield [`ParserEvent`]s that signal a
+    /// top-level `Bail out!` the moment its line arrives, e.g. for a live dashboard to stop waiting for more tests.
+    /// Any pending construct is yielded before the [`ParserEvent::BailOut`], and any line pushed afterwards as
+    /// [`ParserEvent::Trailing`], without being parsed. A bail-out nested inside a subtest is part of that subtest's
+    /// statement instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::{OwnedStatement, ParserEvent, StreamingParser};
+    ///
+    /// let mut parser = StreamingParser::new();
+    /// assert!(parser.push_line_events("TAP version 14").unwrap().is_empty());
+    /// assert!(parser.push_line_events("ok 1 - foo").unwrap().is_empty());
+    ///
+    /// let events = parser.push_line_events("Bail out! database down").unwrap();
+    /// assert!(matches!(&events[0], ParserEvent::Statement(s) if matches!(**s, OwnedStatement::Test(_))));
+    /// assert_eq!(events[1], ParserEvent::BailOut(Some("database down".to_owned())));
+    /// assert!(parser.bailed_out());
+    ///
+    /// let events = parser.push_line_events("ok 2 - bar").unwrap();
+    /// assert_eq!(events, [ParserEvent::Trailing("ok 2 - bar".to_owned())]);
+    /// ```
+    pub fn push_line_events(&mut self, line: &str) -> Result<Vec<ParserEvent>> {
+        if self.bailed_out {
+            let line = line
+                .strip_suffix('\n')
+                .map(|l| l.strip_suffix('\r').unwrap_or(l))
+                .unwrap_or(line);
+            self.lines += 1;
+            self.offset += line.len() + 1;
+            return Ok(vec![ParserEvent::Trailing(line.to_owned())]);
+        }
+        let statements = self.push_line(line)?;
+        Ok(statements
+            .into_iter()
+            .map(|statement| match statement {
+                OwnedStatement::BailOut(bail_out) => {
+                    self.bailed_out = true;
+                    ParserEvent::BailOut(bail_out.reason)
+                }
+                statement => ParserEvent::Statement(Box::new(statement)),
+            })
+            .collect())
+    }
+
+    /// Whether a top-level bail-out was reported by [`StreamingParser::push_line_events`].
+    pub fn bailed_out(&self) -> bool {
+        self.bailed_out
+    }
+
+    /// Signal the end of input, returning the pending [`OwnedStatement`], if any.
+    pub fn finish(mut self) -> Result<Vec<OwnedStatement>> {
+        Ok(match self.state {
+            State::Idle => Vec::new(),
+            _ => vec![self.flush()?],
+        })
+    }
+
+    /// Handle `line` while no construct is pending.
+    fn start(&mut self, line: &str) -> Result<Option<OwnedStatement>> {
+        let trimmed = line.trim_end();
+        let is_subtest_decl = is_subtest_decl(trimmed);
+
+        if trimmed.is_empty() {
+            Ok(None)
+        } else if is_subtest_decl || line.starts_with([' ', '\t']) {
+            self.begin(line, State::Subtest);
+            Ok(None)
+        } else if starts_with_ignore_ascii_case(trimmed, "ok")
+            || starts_with_ignore_ascii_case(trimmed, "not ok")
+        {
+            self.begin(line, State::Test);
+            Ok(None)
+        } else if trimmed.starts_with('#') {
+            Ok(None)
+        } else if starts_with_ignore_ascii_case(trimmed, "tap version") {
+            self.preamble = Some(Preamble::parse_from_str(trimmed)?.into_owned());
+            Ok(None)
+        } else if trimmed.starts_with(|c: char| c.is_ascii_digit())
+            && Plan::parse_from_str(trimmed).is_ok()
+        {
+            self.plan = Some(Plan::parse_from_str(trimmed)?.into_owned());
+            Ok(None)
+        } else {
+            self.begin(line, State::Idle);
+            self.flush().map(Some)
+        }
+    }
+
+    fn begin(&mut self, line: &str, state: State) {
+        self.buffer.clear();
+        self.buffer_start = self.offset;
+        self.buffer_line = self.lines;
+        self.state = state;
+        self.append(line);
+    }
+
+    fn append(&mut self, line: &str) {
+        self.buffer.push_str(line);
+        self.buffer.push('\n');
+    }
+
+    /// Parse the pending construct and reset to [`State::Idle`].
+    fn flush(&mut self) -> Result<OwnedStatement> {
+        self.state = State::Idle;
+        let mut statement = Statement::parse_from_str(&self.buffer)?.into_owned();
+        shift(&mut statement, self.buffer_start, self.buffer_line - 1);
+        if let OwnedStatement::Test(test) = &mut statement {
+            self.number = test.number.unwrap_or_else(|| self.number.saturating_add(1));
+            test.effective_number = self.number;
+        }
+        Ok(statement)
+    }
+}
+
+fn starts_with_ignore_ascii_case(s: &str, prefix: &str) -> bool {
+    s.len() >= prefix.len()
+        && s.is_char_boundary(prefix.len())
+        && s[..prefix.len()].eq_ignore_ascii_case(prefix)
+}
+
+/// Move the spans of `statement`, parsed from a chunk of the stream, to their position within the whole stream.
+fn shift(statement: &mut OwnedStatement, bytes: usize, lines: usize) {
+    match statement {
+        OwnedStatement::Anything(_) | OwnedStatement::Comment(_) => {}
+        OwnedStatement::BailOut(b) => b.span.shift(bytes, lines),
+        OwnedStatement::Pragma(p) => p.span.shift(bytes, lines),
+        OwnedStatement::Test(t) => t.span.shift(bytes, lines),
+        OwnedStatement::Subtest(s) => {
+            s.span.shift(bytes, lines);
+            for statement in &mut s.body {
+                shift(statement, bytes, lines);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+    use std::fs;
+
+    use super::*;
+
+    fn stream(content: &str) -> (StreamingParser, Vec<OwnedStatement>) {
+        let mut parser = StreamingParser::new();
+        let mut statements = Vec::new();
+        for line in content.lines() {
+            statements.extend(parser.push_line(line).expect("Parser error"));
+        }
+        (parser, statements)
+    }
+
+    #[test]
+    fn test_stream_matches_document() {
+        for example in ["cascading", "common", "yaml", "move_mount_set_group_test"] {
+            let contents = fs::read_to_string(format!("examples/{}.tap", example))
+                .expect("Failed to read file");
+            let (parser, mut statements) = stream(&contents);
+            let preamble = parser.preamble().map(|p| p.version.clone());
+            statements.extend(parser.finish().expect("Parser error"));
+
+            let doc = crate::Document::parse_from_str(&contents).expect("Parser error");
+            assert_eq!(preamble.as_deref(), Some(doc.preamble.version));
+            let expected: Vec<_> = doc.body.iter().map(OwnedStatement::from).collect();
+            assert_eq!(statements, expected, "{}", example);
+        }
+    }
+
+    #[test]
+    fn test_stream_non_ascii_yaml() {
+        /* The anchor's byte count would end inside `é`, which must not be mistaken for a line to slice. */
+        let mut parser = StreamingParser::new();
+        for line in ["TAP version 14", "not ok 1", "  ---", "aé: x"] {
+            let _ = parser.push_line(line);
+        }
+
+        let content = concat!(
+            "TAP version 14\n",
+            "1..1\n",
+            "not ok 1 - café\n",
+            "  ---\n",
+            "  message: déjà vu\n",
+            "  ...\n",
+        );
+        let (parser, mut statements) = stream(content);
+        statements.extend(parser.finish().expect("Parser error"));
+        let doc = crate::Document::parse_from_str(content).expect("Parser error");
+        let expected: Vec<_> = doc.body.iter().map(OwnedStatement::from).collect();
+        assert_eq!(statements, expected);
+    }
+
+    #[test]
+    fn test_stream_yields_early() {
+        let mut parser = StreamingParser::new();
+        assert!(parser.push_line("TAP version 14\n").unwrap().is_empty());
+        assert!(parser.push_line("# Subtest: sub\n").unwrap().is_empty());
+        assert!(parser.push_line("    1..1\n").unwrap().is_empty());
+        assert!(parser.push_line("    ok 1\n").unwrap().is_empty());
+        assert!(parser.push_line("ok 1 - sub\n").unwrap().len() == 1);
+        let statements = parser.push_line("Bail out! boom\n").unwrap();
+        assert_eq!(statements.len(), 2);
+        assert!(matches!(statements[0], OwnedStatement::Test(_)));
+        assert!(matches!(&statements[1], OwnedStatement::BailOut(b) if b.span.start_line == 6));
+        assert!(parser.finish().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_stream_subtest_markers() {
+        let (parser, statements) = stream(concat!(
+            "#SUBTEST:Foo\n",
+            "    1..1\n",
+            "    ok 1\n",
+            "# Subtests follow\n",
+            "ok 1\n",
+        ));
+        assert!(
+            matches!(&statements[..], [OwnedStatement::Subtest(s)] if s.name.as_deref() == Some("Foo"))
+        );
+        assert!(matches!(
+            &parser.finish().unwrap()[..],
+            [OwnedStatement::Test(_)]
+        ));
+    }
+
+    #[test]
+    fn test_stream_bail_out_events() {
+        let mut parser = StreamingParser::new();
+        let mut events = Vec::new();
+        for line in [
+            "TAP version 14",
+            "# Subtest: sub",
+            "    1..2",
+            "    ok 1",
+            "    Bail out! nested",
+            "Bail out!",
+            "ok 1 - sub",
+            "",
+        ] {
+            events.extend(parser.push_line_events(line).expect("Parser error"));
+        }
+        let ParserEvent::Statement(subtest) = &events[0] else {
+            panic!("Expected a statement, got {:?}", events[0]);
+        };
+        assert!(matches!(
+            &**subtest,
+            OwnedStatement::Subtest(s)
+                if matches!(s.body[..], [OwnedStatement::Test(_), OwnedStatement::BailOut(_)])
+        ));
+        assert_eq!(
+            events[1..],
+            [
+                ParserEvent::BailOut(None),
+                ParserEvent::Trailing("ok 1 - sub".to_owned()),
+                ParserEvent::Trailing(String::new()),
+            ]
+        );
+        assert!(parser.finish().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,314 @@
+//! Subunit v2 export, for consumers of the binary subunit protocol.
+
+use alloc::{format, vec::Vec};
+
+use crate::{Document, Key, Statement, Test};
+
+const SIGNATURE: u8 = 0xb3;
+const VERSION: u16 = 0x2000;
+const FLAG_TEST_ID: u16 = 0x0800;
+const FLAG_RUNNABLE: u16 = 0x0100;
+const FLAG_FILE_CONTENT: u16 = 0x0040;
+const FLAG_MIME_TYPE: u16 = 0x0020;
+const FLAG_EOF: u16 = 0x0010;
+
+const STATUS_UNDEFINED: u16 = 0;
+const STATUS_INPROGRESS: u16 = 2;
+const STATUS_SUCCESS: u16 = 3;
+const STATUS_UXSUCCESS: u16 = 4;
+const STATUS_SKIP: u16 = 5;
+const STATUS_FAIL: u16 = 6;
+const STATUS_XFAIL: u16 = 7;
+
+/// Largest chunk of file content put into a single packet, keeping packets well below the protocol's 4 MiB limit.
+const CHUNK_SIZE: usize = 65536;
+
+/// CRC32 (IEEE 802.3) of `bytes`, as used to checksum each packet.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (!(crc & 1)).wrapping_add(1));
+        }
+    }
+    !crc
+}
+
+/// Largest number representable by [`write_number`].
+const NUMBER_MAX: usize = 0x3fff_ffff;
+
+/// Append `n` to `packet` as a variable length number, its two most significant bits holding the count of extra
+/// bytes. Panics if `n` exceeds [`NUMBER_MAX`].
+fn write_number(packet: &mut Vec<u8>, n: usize) {
+    match n {
+        0..=0x3f => packet.push(n as u8),
+        0x40..=0x3fff => packet.extend_from_slice(&(0x4000 | n as u16).to_be_bytes()),
+        0x4000..=0x3f_ffff => packet.extend_from_slice(&(0x80_0000 | n as u32).to_be_bytes()[1..]),
+        0x40_0000..=NUMBER_MAX => packet.extend_from_slice(&(0xc000_0000 | n as u32).to_be_bytes()),
+        _ => panic!("{} exceeds the largest subunit number {}", n, NUMBER_MAX),
+    }
+}
+
+/// Append `s` to `packet`, prefixed by its length.
+fn write_utf8(packet: &mut Vec<u8>, s: &str) {
+    write_number(packet, s.len());
+    packet.extend_from_slice(s.as_bytes());
+}
+
+/// A file attached to a packet.
+struct File<'f> {
+    name: &'f str,
+    mime_type: &'f str,
+    content: &'f [u8],
+    eof: bool,
+}
+
+/// Append a packet reporting `status` of the test `id` to `stream`, optionally attaching (a chunk of) a `file`.
+fn write_packet(stream: &mut Vec<u8>, id: &str, status: u16, file: Option<File>) {
+    let mut flags = VERSION | FLAG_TEST_ID | status;
+    let mut body = Vec::new();
+    write_utf8(&mut body, id);
+    if status != STATUS_UNDEFINED {
+        flags |= FLAG_RUNNABLE;
+    }
+    if let Some(file) = file {
+        flags |= FLAG_MIME_TYPE | FLAG_FILE_CONTENT;
+        write_utf8(&mut body, file.mime_type);
+        write_utf8(&mut body, file.name);
+        write_number(&mut body, file.content.len());
+        body.extend_from_slice(file.content);
+        if file.eof {
+            flags |= FLAG_EOF;
+        }
+    }
+
+    /* The length covers the whole packet, including the length field itself and the trailing CRC. */
+    let length = 3 + body.len() + 4;
+    let length = match length {
+        0..=0x3e => length + 1,
+        0x3f..=0x3ffd => length + 2,
+        _ => length + 3,
+    };
+    let start = stream.len();
+    stream.push(SIGNATURE);
+    stream.extend_from_slice(&flags.to_be_bytes());
+    write_number(stream, length);
+    stream.append(&mut body);
+    let crc = crc32(&stream[start..]);
+    stream.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Subunit status of `test`. TODO tests are expected to fail, so a failing one is an `xfail`, a passing one an
+/// unexpected success.
+fn status(test: &Test) -> u16 {
+    match (test.directive.as_ref().map(|d| d.key), test.result) {
+        (Some(Key::Skip), _) => STATUS_SKIP,
+        (Some(Key::Todo), false) => STATUS_XFAIL,
+        (Some(Key::Todo), true) => STATUS_UXSUCCESS,
+        (_, true) => STATUS_SUCCESS,
+        (_, false) => STATUS_FAIL,
+    }
+}
+
+/// Append the packets of each test in `statements` to `stream`, recursing into subtests. `path` holds the dotted
+/// numbers of the enclosing subtests.
+fn write_tests(stream: &mut Vec<u8>, statements: &[Statement], path: &str) {
+    let mut number = 0;
+    for (index, statement) in statements.iter().enumerate() {
+        match statement {
+            Statement::Test(test) => {
+                number = test.effective_number;
+                let id = match test.description.as_deref().map(str::trim) {
+                    Some(description) if !description.is_empty() => {
+                        format!("{}{} - {}", path, number, description)
+                    }
+                    _ => format!("{}{}", path, number),
+                };
+                write_packet(stream, &id, STATUS_INPROGRESS, None);
+                if !test.yaml.is_empty() {
+                    let yaml = test.yaml.join("\n");
+                    let chunks: Vec<_> = yaml.as_bytes().chunks(CHUNK_SIZE).collect();
+                    for (i, chunk) in chunks.iter().enumerate() {
+                        let file = File {
+                            name: "yaml",
+                            mime_type: "application/yaml; charset=utf8",
+                            content: chunk,
+                            eof: i + 1 == chunks.len(),
+                        };
+                        write_packet(stream, &id, STATUS_UNDEFINED, Some(file));
+                    }
+                }
+                write_packet(stream, &id, status(test), None);
+            }
+            Statement::Subtest(subtest) => {
+                /* A subtest is numbered by the test closing it. */
+                let closing = statements[index + 1..].iter().find_map(|s| match s {
+                    Statement::Test(test) => Some(test.effective_number),
+                    _ => None,
+                });
+                let path = format!(
+                    "{}{}.",
+                    path,
+                    closing.unwrap_or_else(|| number.saturating_add(1))
+                );
+                write_tests(stream, &subtest.body, &path);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Document<'_> {
+    /// Render the document as a [subunit v2](https://github.com/testing-cabal/subunit) packet stream.
+    ///
+    /// Each [`crate::Test`] yields an `inprogress` packet followed by a packet holding its final status: `success` or
+    /// `fail` by its result, `skip` for a [`Key::Skip`] directive, and `xfail` or `uxsuccess` for a [`Key::Todo`]
+    /// directive. A test's YAML block is attached in between as a file named `yaml`. Test IDs are the dotted numbers
+    /// leading to the test followed by its description, e.g. `1.2 - foo` for test `2` inside the [`crate::Subtest`]
+    /// closed by test `1`. Tests nested inside a subtest precede the test closing it.
+    ///
+    /// Only available with the `subunit` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a test ID is longer than the protocol can encode, i.e. 1 GiB.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!("TAP version 14\n", "1..1\n", "ok 1 - foo\n");
+    /// let stream = Document::parse_from_str(content).expect("Parser error").to_subunit();
+    /// /* Signature, flags of an `inprogress` packet, packet length and test ID. */
+    /// assert_eq!(stream[..5], [0xb3, 0x29, 0x02, 0x10, 0x07]);
+    /// assert_eq!(&stream[5..12], b"1 - foo");
+    /// /* Followed by a `success` packet. */
+    /// assert_eq!(stream[16..19], [0xb3, 0x29, 0x03]);
+    /// assert_eq!(stream.len(), 32);
+    /// ```
+    pub fn to_subunit(&self) -> Vec<u8> {
+        let mut stream = Vec::new();
+        write_tests(&mut stream, &self.body, "");
+        stream
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec};
+
+    use super::*;
+
+    /// Split `stream` into the flags, test ID and file content of each packet, checking lengths and CRCs.
+    fn decode(mut stream: &[u8]) -> Vec<(u16, String, Option<String>)> {
+        fn number(bytes: &mut &[u8]) -> usize {
+            let extra = usize::from(bytes[0] >> 6);
+            let mut n = usize::from(bytes[0] & 0x3f);
+            for byte in &bytes[1..=extra] {
+                n = n << 8 | usize::from(*byte);
+            }
+            *bytes = &bytes[extra + 1..];
+            n
+        }
+        fn utf8(bytes: &mut &[u8]) -> String {
+            let len = number(bytes);
+            let s = String::from_utf8(bytes[..len].to_vec()).unwrap();
+            *bytes = &bytes[len..];
+            s
+        }
+
+        let mut packets = Vec::new();
+        while !stream.is_empty() {
+            assert_eq!(stream[0], SIGNATURE);
+            let flags = u16::from_be_bytes([stream[1], stream[2]]);
+            let mut rest = &stream[3..];
+            let length = number(&mut rest);
+            let (packet, tail) = stream.split_at(length);
+            let (content, crc) = packet.split_at(length - 4);
+            assert_eq!(crc32(content).to_be_bytes(), crc);
+
+            let mut rest = &rest[..rest.len() - tail.len() - 4];
+            let id = utf8(&mut rest);
+            let file = (flags & FLAG_FILE_CONTENT != 0).then(|| {
+                utf8(&mut rest);
+                utf8(&mut rest);
+                utf8(&mut rest)
+            });
+            assert!(rest.is_empty());
+            packets.push((flags, id, file));
+            stream = tail;
+        }
+        packets
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn test_write_number() {
+        for (n, expected) in [
+            (0x3f, vec![0x3f]),
+            (0x40, vec![0x40, 0x40]),
+            (0x3fff, vec![0x7f, 0xff]),
+            (0x4000, vec![0x80, 0x40, 0x00]),
+            (0x40_0000, vec![0xc0, 0x40, 0x00, 0x00]),
+            (NUMBER_MAX, vec![0xff, 0xff, 0xff, 0xff]),
+        ] {
+            let mut bytes = Vec::new();
+            write_number(&mut bytes, n);
+            assert_eq!(bytes, expected);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds the largest subunit number")]
+    fn test_write_number_overflow() {
+        write_number(&mut Vec::new(), NUMBER_MAX + 1);
+    }
+
+    #[test]
+    fn test_subunit_statuses() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..4\n",
+            "# Subtest: sub\n",
+            "    1..1\n",
+            "    ok 1 - inner # SKIP not today\n",
+            "ok 1 - sub\n",
+            "not ok 2 - boom\n",
+            "  ---\n",
+            "  message: boom\n",
+            "  ...\n",
+            "not ok 3 # TODO later\n",
+            "ok 4 # TODO fixed\n",
+        );
+        let stream = Document::parse_from_str(content)
+            .expect("Parser error")
+            .to_subunit();
+        let packets: Vec<_> = decode(&stream)
+            .into_iter()
+            .map(|(flags, id, file)| (flags & 0x7, id, file))
+            .collect();
+        let id = String::from;
+        assert_eq!(
+            packets,
+            [
+                (STATUS_INPROGRESS, id("1.1 - inner"), None),
+                (STATUS_SKIP, id("1.1 - inner"), None),
+                (STATUS_INPROGRESS, id("1 - sub"), None),
+                (STATUS_SUCCESS, id("1 - sub"), None),
+                (STATUS_INPROGRESS, id("2 - boom"), None),
+                (STATUS_UNDEFINED, id("2 - boom"), Some(id("message: boom"))),
+                (STATUS_FAIL, id("2 - boom"), None),
+                (STATUS_INPROGRESS, id("3"), None),
+                (STATUS_XFAIL, id("3"), None),
+                (STATUS_INPROGRESS, id("4"), None),
+                (STATUS_UXSUCCESS, id("4"), None),
+            ]
+        );
+    }
+}
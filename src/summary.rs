@@ -0,0 +1,288 @@
+//! Aggregated statistics over a parsed [`Document`].
+
+use core::{
+    fmt::{self, Display, Formatter},
+    time::Duration,
+};
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::{duration::total_duration, Document, Key, Result, Statement, TapError};
+
+/// How [`Document::summary_with_policy`] counts a `not ok` test with a [`Key::Skip`] directive, see
+/// [`crate::Test::is_skipped_failure`]. Such a test is contradictory, and harnesses interpret it differently.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SkipFailPolicy {
+    /// Count the test as skipped only, like `prove` does.
+    #[default]
+    AsSkip,
+    /// Count the test as failed only.
+    AsFail,
+    /// Refuse to summarize, failing with [`TapError::SkippedFailure`].
+    Error,
+}
+
+/// Counts of [`crate::Test`] outcomes, see [`Document::summary`].
+///
+/// The counts are independent of each other: a `not ok` test with a [`Key::Todo`] directive counts as both `failed` and
+/// `todo`, so callers can follow the convention that TODO failures don't break the build. The exception is a `not ok`
+/// test with a [`Key::Skip`] directive, which counts as either `failed` or `skipped` by [`SkipFailPolicy`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Summary<'a> {
+    /// Number of tests.
+    pub total: usize,
+    /// Number of `ok` tests.
+    pub passed: usize,
+    /// Number of `not ok` tests.
+    pub failed: usize,
+    /// Number of tests with a [`Key::Skip`] directive.
+    pub skipped: usize,
+    /// Number of tests with a [`Key::Todo`] directive.
+    pub todo: usize,
+    /// Whether a [`crate::BailOut`] was encountered, see [`Document::bail_out_nested`] for finding it.
+    pub bailed_out: bool,
+    /// Whether the document's [`crate::Plan`] is a skip-all plan, see [`crate::Plan::is_skip_all`].
+    pub skip_all: bool,
+    /// The reason given by a skip-all plan, without a leading `SKIP` keyword.
+    pub skip_all_reason: Option<&'a str>,
+    /// Sum of the durations reported by the tests' YAML blocks, see [`crate::Test::duration`]. The closing test of a
+    /// subtest reporting a duration covers the tests nested inside, which aren't counted again. Saturates at
+    /// [`Duration::MAX`], or is [`None`] if no test reported a duration.
+    pub total_duration: Option<Duration>,
+}
+
+impl Summary<'_> {
+    fn add(&mut self, statements: &[Statement], policy: SkipFailPolicy) -> Result<()> {
+        for statement in statements {
+            match statement {
+                Statement::Test(test) => {
+                    self.total += 1;
+                    if test.is_skipped_failure() {
+                        match policy {
+                            SkipFailPolicy::AsSkip => self.skipped += 1,
+                            SkipFailPolicy::AsFail => self.failed += 1,
+                            SkipFailPolicy::Error => {
+                                return Err(TapError::SkippedFailure(test.span.start_line))
+                            }
+                        }
+                        continue;
+                    }
+                    if test.result {
+                        self.passed += 1;
+                    } else {
+                        self.failed += 1;
+                    }
+                    match test.directive.as_ref().map(|d| d.key) {
+                        Some(Key::Skip) => self.skipped += 1,
+                        Some(Key::Todo) => self.todo += 1,
+                        Some(Key::Other(_)) | None => {}
+                    }
+                }
+                Statement::Subtest(subtest) => self.add(&subtest.body, policy)?,
+                Statement::BailOut(_) => self.bailed_out = true,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Renders a compact, human readable line, e.g. `5 tests, 4 passed, 1 failed, 1 skipped, 0 todo`.
+impl Display for Summary<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} test{}, {} passed, {} failed, {} skipped, {} todo",
+            self.total,
+            if self.total == 1 { "" } else { "s" },
+            self.passed,
+            self.failed,
+            self.skipped,
+            self.todo
+        )?;
+        if self.bailed_out {
+            f.write_str(", bailed out")?;
+        }
+        if self.skip_all {
+            f.write_str(", all skipped")?;
+            if let Some(reason) = self.skip_all_reason {
+                write!(f, ": {}", reason)?;
+            }
+        }
+        if let Some(duration) = self.total_duration {
+            write!(f, ", took {:.3}s", duration.as_secs_f64())?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Document<'a> {
+    /// Count the outcomes of all [`crate::Test`]s, including those nested inside [`crate::Subtest`]s.
+    ///
+    /// A skip-all plan (`1..0`) of the document is reported by [`Summary::skip_all`], along with its reason.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..4\n",
+    ///     "ok 1 - foo()\n",
+    ///     "not ok 2 - bar() # TODO not implemented\n",
+    ///     "ok 3 - baz() # SKIP unsupported\n",
+    ///     "not ok 4 - qux()\n",
+    /// );
+    /// let summary = Document::parse_from_str(content).expect("Parser error").summary();
+    /// assert_eq!(summary.total, 4);
+    /// assert_eq!(summary.passed, 2);
+    /// assert_eq!(summary.failed, 2);
+    /// assert_eq!(summary.skipped, 1);
+    /// assert_eq!(summary.todo, 1);
+    /// assert!(!summary.bailed_out);
+    /// ```
+    pub fn summary(&self) -> Summary<'a> {
+        self.summary_with_policy(SkipFailPolicy::AsSkip)
+            .expect("Only SkipFailPolicy::Error fails")
+    }
+
+    /// Count the outcomes of all [`crate::Test`]s like [`Document::summary`], counting a `not ok` test with a
+    /// [`Key::Skip`] directive by `policy`. Callers wanting yet another interpretation can find such tests by
+    /// [`crate::Test::is_skipped_failure`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::{Document, SkipFailPolicy, TapError};
+    ///
+    /// let content = concat!("TAP version 14\n", "1..2\n", "ok 1\n", "not ok 2 # SKIP no network\n");
+    /// let doc = Document::parse_from_str(content).expect("Parser error");
+    ///
+    /// let summary = doc.summary_with_policy(SkipFailPolicy::AsSkip).unwrap();
+    /// assert_eq!((summary.failed, summary.skipped), (0, 1));
+    /// let summary = doc.summary_with_policy(SkipFailPolicy::AsFail).unwrap();
+    /// assert_eq!((summary.failed, summary.skipped), (1, 0));
+    /// let err = doc.summary_with_policy(SkipFailPolicy::Error).unwrap_err();
+    /// assert!(matches!(err, TapError::SkippedFailure(4)));
+    /// ```
+    pub fn summary_with_policy(&self, policy: SkipFailPolicy) -> Result<Summary<'a>> {
+        let mut summary = Summary::default();
+        summary.add(&self.body, policy)?;
+        summary.total_duration = total_duration(&self.body);
+        if self.plan.is_skip_all() {
+            summary.skip_all = true;
+            summary.skip_all_reason = self.plan.skip_reason();
+        }
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_summary_cascading() {
+        let contents = fs::read_to_string("examples/cascading.tap").expect("Failed to read file");
+        let doc = Document::parse_from_str(&contents).expect("Parser error");
+        let summary = doc.summary();
+        assert_eq!(
+            summary,
+            Summary {
+                total: 5,
+                passed: 4,
+                failed: 0,
+                skipped: 1,
+                todo: 0,
+                bailed_out: false,
+                skip_all: false,
+                skip_all_reason: None,
+                total_duration: None,
+            }
+        );
+        assert_eq!(
+            summary.to_string(),
+            "5 tests, 4 passed, 0 failed, 1 skipped, 0 todo"
+        );
+
+        /* Test 4 is `not ok # SKIP`. */
+        let summary = doc.summary_with_policy(SkipFailPolicy::AsFail).unwrap();
+        assert_eq!((summary.failed, summary.skipped), (1, 0));
+        assert!(matches!(
+            doc.summary_with_policy(SkipFailPolicy::Error),
+            Err(TapError::SkippedFailure(9))
+        ));
+    }
+
+    #[test]
+    fn test_summary_bail_out() {
+        let content = concat!("TAP version 14\n", "1..2\n", "ok 1\n", "Bail out! boom\n");
+        let summary = Document::parse_from_str(content)
+            .expect("Parser error")
+            .summary();
+        assert_eq!(summary.total, 1);
+        assert!(summary.bailed_out);
+        assert_eq!(
+            summary.to_string(),
+            "1 test, 1 passed, 0 failed, 0 skipped, 0 todo, bailed out"
+        );
+    }
+
+    #[test]
+    fn test_summary_skip_all() {
+        let content = concat!("TAP version 14\n", "1..0 # Skip no network\n");
+        let summary = Document::parse_from_str(content)
+            .expect("Parser error")
+            .summary();
+        assert!(summary.skip_all);
+        assert_eq!(summary.skip_all_reason, Some("no network"));
+        assert_eq!(
+            summary.to_string(),
+            "0 tests, 0 passed, 0 failed, 0 skipped, 0 todo, all skipped: no network"
+        );
+    }
+
+    #[test]
+    fn test_summary_total_duration() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..3\n",
+            "ok 1 - timed\n",
+            "  ---\n",
+            "  duration_ms: 250\n",
+            "  ...\n",
+            "# Subtest: covered by its closing test\n",
+            "    1..1\n",
+            "    ok 1\n",
+            "      ---\n",
+            "      duration_ms: 900\n",
+            "      ...\n",
+            "ok 2 - covered by its closing test\n",
+            "  ---\n",
+            "  duration_ms: 1000\n",
+            "  ...\n",
+            "# Subtest: summed up\n",
+            "    1..2\n",
+            "    ok 1\n",
+            "      ---\n",
+            "      time: 0.5s\n",
+            "      ...\n",
+            "    ok 2\n",
+            "ok 3 - summed up\n",
+        );
+        let summary = Document::parse_from_str(content)
+            .expect("Parser error")
+            .summary();
+        assert_eq!(summary.total_duration, Some(Duration::from_millis(1750)));
+        assert_eq!(
+            summary.to_string(),
+            "6 tests, 6 passed, 0 failed, 0 skipped, 0 todo, took 1.750s"
+        );
+    }
+}
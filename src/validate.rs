@@ -0,0 +1,546 @@
+//! Semantic checks performed on an already parsed [`Document`].
+
+use alloc::{borrow::ToOwned, collections::BTreeSet, format, string::String, vec::Vec};
+use core::fmt::{self, Display, Formatter};
+#[cfg(feature = "std")]
+use std::error::Error;
+
+use crate::{Document, Plan, Result, Span, Statement, TapError, Test};
+
+/// Controls whether a validation finding is reported as a hard error or merely returned as a warning.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strictness {
+    /// Findings are returned to the caller, who decides what to do with them.
+    #[default]
+    Lenient,
+    /// The first finding is turned into a [`TapError`].
+    Strict,
+}
+
+/// The first divergence found by [`Document::assert_matches`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// 0-based index of the top-level test at which expectation and document diverge.
+    pub index: usize,
+    /// Expected `(number, result)` pair, or [`None`] if the document holds more tests than expected.
+    pub expected: Option<(i32, bool)>,
+    /// Actual `(number, result)` pair, or [`None`] if the document holds fewer tests than expected.
+    pub actual: Option<(i32, bool)>,
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fn describe(pair: Option<(i32, bool)>) -> String {
+            match pair {
+                Some((number, true)) => format!("ok {}", number),
+                Some((number, false)) => format!("not ok {}", number),
+                None => "no test".to_owned(),
+            }
+        }
+
+        write!(
+            f,
+            "Test at index {}: expected {}, found {}",
+            self.index,
+            describe(self.expected),
+            describe(self.actual)
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for Mismatch {}
+
+/// A [`Plan`] not matching the number of tests that actually appeared, found by [`Document::validate_plan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanMismatch {
+    /// Names of the [`crate::Subtest`]s leading to the offending plan, outermost first. Empty for the document's own
+    /// plan, unnamed subtests are represented by an empty name.
+    pub path: Vec<String>,
+    /// Number of tests announced by the plan.
+    pub expected: usize,
+    /// Number of tests that appeared.
+    pub counted: usize,
+    /// Whether the plan is a skip-all plan (`1..0`), which must not be followed by any test.
+    pub skip_all: bool,
+}
+
+impl Display for PlanMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            f.write_str("Plan")?;
+        } else {
+            write!(f, "Plan of subtest '{}'", self.path.join("/"))?;
+        }
+        if self.skip_all {
+            write!(f, " skips all tests, but {} appeared", self.counted)
+        } else {
+            write!(
+                f,
+                " expects {} tests, but {} appeared",
+                self.expected, self.counted
+            )
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for PlanMismatch {}
+
+/// Check the tests directly within `statements` against `plan`, then recurse into nested subtests.
+fn validate_scope(
+    plan: &Plan,
+    statements: &[Statement],
+    path: &mut Vec<String>,
+) -> core::result::Result<(), PlanMismatch> {
+    let expected = plan.count();
+    let counted = statements
+        .iter()
+        .filter(|s| matches!(s, Statement::Test(_)))
+        .count();
+    if expected != counted {
+        return Err(PlanMismatch {
+            path: path.clone(),
+            expected,
+            counted,
+            skip_all: plan.is_skip_all(),
+        });
+    }
+    for statement in statements {
+        if let Statement::Subtest(subtest) = statement {
+            path.push(subtest.name.unwrap_or_default().to_owned());
+            validate_scope(&subtest.plan, &subtest.body, path)?;
+            path.pop();
+        }
+    }
+    Ok(())
+}
+
+/// The kind of a [`NumberingIssue`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberingIssueKind {
+    /// The numbers `first..=last` never appeared, although later numbers did.
+    Gap {
+        /// First missing number.
+        first: i32,
+        /// Last missing number.
+        last: i32,
+    },
+    /// The number was already used by an earlier test.
+    Duplicate {
+        /// The repeated number.
+        number: i32,
+        /// Location of the repeating test.
+        span: Span,
+    },
+    /// The number is lower than the number of the preceding test.
+    OutOfOrder {
+        /// The offending number.
+        number: i32,
+        /// Number of the preceding test.
+        previous: i32,
+        /// Location of the offending test.
+        span: Span,
+    },
+}
+
+/// A test numbering defect found by [`Document::check_numbering`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumberingIssue {
+    /// Names of the [`crate::Subtest`]s leading to the affected plan scope, outermost first. Empty for the document's
+    /// top-level, unnamed subtests are represented by an empty name.
+    pub path: Vec<String>,
+    /// What is wrong with the numbering.
+    pub kind: NumberingIssueKind,
+}
+
+impl Display for NumberingIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            NumberingIssueKind::Gap { first, last } if first == last => {
+                write!(f, "Test {} is missing", first)?
+            }
+            NumberingIssueKind::Gap { first, last } => {
+                write!(f, "Tests {} to {} are missing", first, last)?
+            }
+            NumberingIssueKind::Duplicate { number, .. } => {
+                write!(f, "Test {} appears more than once", number)?
+            }
+            NumberingIssueKind::OutOfOrder {
+                number, previous, ..
+            } => write!(f, "Test {} follows test {}", number, previous)?,
+        }
+        if !self.path.is_empty() {
+            write!(f, " in subtest '{}'", self.path.join("/"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Collect numbering issues of the tests directly within `statements`, then recurse into nested subtests.
+fn check_scope(
+    plan: &Plan,
+    statements: &[Statement],
+    path: &mut Vec<String>,
+    issues: &mut Vec<NumberingIssue>,
+) {
+    let mut issue = |kind| {
+        issues.push(NumberingIssue {
+            path: path.clone(),
+            kind,
+        })
+    };
+
+    let mut seen = BTreeSet::new();
+    let mut previous = None;
+    for (number, test) in resolve_numbers(statements) {
+        if !seen.insert(number) {
+            issue(NumberingIssueKind::Duplicate {
+                number,
+                span: test.span,
+            });
+        } else if let Some(previous) = previous.filter(|&p| number < p) {
+            issue(NumberingIssueKind::OutOfOrder {
+                number,
+                previous,
+                span: test.span,
+            });
+        }
+        previous = Some(number);
+    }
+
+    let mut expected = plan.first;
+    for &number in seen.range(plan.first..) {
+        if number > expected {
+            issue(NumberingIssueKind::Gap {
+                first: expected,
+                last: number - 1,
+            });
+        }
+        expected = number.saturating_add(1);
+    }
+
+    for statement in statements {
+        if let Statement::Subtest(subtest) = statement {
+            path.push(subtest.name.unwrap_or_default().to_owned());
+            check_scope(&subtest.plan, &subtest.body, path, issues);
+            path.pop();
+        }
+    }
+}
+
+/// Resolve the number of each test in `statements`. Tests without an explicit number continue counting from the
+/// previous test, explicit numbers reset the running counter.
+pub(crate) fn resolve_numbers<'b, 'a>(statements: &'b [Statement<'a>]) -> Vec<(i32, &'b Test<'a>)> {
+    let mut counter: i32 = 0;
+    statements
+        .iter()
+        .filter_map(|s| match s {
+            Statement::Test(t) => {
+                counter = t.number.unwrap_or_else(|| counter.saturating_add(1));
+                Some((counter, t))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+impl<'a> Document<'a> {
+    /// Check each top-level [`Test`]'s `(number, result)` against `expected`, reporting the first divergence.
+    ///
+    /// Tests without an explicit number are numbered sequentially, following the preceding test.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..3\n",
+    ///     "ok 1 - foo()\n",
+    ///     "not ok - bar()\n",
+    ///     "ok 3 - baz()\n",
+    /// );
+    /// let doc = Document::parse_from_str(content).expect("Parser error");
+    /// assert!(doc.assert_matches(&[(1, true), (2, false), (3, true)]).is_ok());
+    ///
+    /// let mismatch = doc.assert_matches(&[(1, true), (2, true)]).unwrap_err();
+    /// assert_eq!(mismatch.index, 1);
+    /// assert_eq!(mismatch.expected, Some((2, true)));
+    /// assert_eq!(mismatch.actual, Some((2, false)));
+    /// ```
+    pub fn assert_matches(&self, expected: &[(i32, bool)]) -> core::result::Result<(), Mismatch> {
+        let actual: Vec<_> = resolve_numbers(&self.body)
+            .into_iter()
+            .map(|(number, t)| (number, t.result))
+            .collect();
+        for index in 0..actual.len().max(expected.len()) {
+            let (expected, actual) = (expected.get(index).copied(), actual.get(index).copied());
+            if expected != actual {
+                return Err(Mismatch {
+                    index,
+                    expected,
+                    actual,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that the number of [`Test`]s matches the [`Plan`], catching truncated output.
+    ///
+    /// Each [`crate::Subtest`] is checked against its own plan, counting only the tests directly within it. The
+    /// document's plan is checked first, followed by the subtests in document order. The first mismatch is returned.
+    /// Note that a [`crate::BailOut`] legitimately cuts a document short, yet is still reported here. A skip-all plan
+    /// (`1..0`, see [`Plan::is_skip_all`]) is valid as long as no test follows it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::Document;
+    ///
+    /// let content = concat!("TAP version 14\n", "1..3\n", "ok 1\n", "ok 2\n");
+    /// let doc = Document::parse_from_str(content).expect("Parser error");
+    /// let mismatch = doc.validate_plan().unwrap_err();
+    /// assert_eq!((mismatch.expected, mismatch.counted), (3, 2));
+    ///
+    /// let content = concat!("TAP version 14\n", "1..0 # no network\n");
+    /// let doc = Document::parse_from_str(content).expect("Parser error");
+    /// assert!(doc.validate_plan().is_ok());
+    /// ```
+    pub fn validate_plan(&self) -> core::result::Result<(), PlanMismatch> {
+        validate_scope(&self.plan, &self.body, &mut Vec::new())
+    }
+
+    /// Report gaps, duplicates and out-of-order numbers among the [`Test`]s of each plan scope.
+    ///
+    /// Tests without an explicit number continue counting from the preceding test. Each [`crate::Subtest`] forms a
+    /// scope of its own. Issues are reported scope by scope, starting with the document's top-level, followed by the
+    /// subtests in document order. Within a scope, duplicates and out-of-order numbers are reported in document order,
+    /// followed by gaps.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::{Document, NumberingIssueKind};
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..4\n",
+    ///     "ok 1\n",
+    ///     "ok 4\n",
+    ///     "ok 3\n",
+    ///     "ok 3\n",
+    /// );
+    /// let doc = Document::parse_from_str(content).expect("Parser error");
+    /// let issues: Vec<_> = doc.check_numbering().into_iter().map(|i| i.to_string()).collect();
+    /// assert_eq!(
+    ///     issues,
+    ///     [
+    ///         "Test 3 follows test 4",
+    ///         "Test 3 appears more than once",
+    ///         "Test 2 is missing",
+    ///     ]
+    /// );
+    /// ```
+    pub fn check_numbering(&self) -> Vec<NumberingIssue> {
+        let mut issues = Vec::new();
+        check_scope(&self.plan, &self.body, &mut Vec::new(), &mut issues);
+        issues
+    }
+
+    /// Find all [`Test`]s, including those nested inside [`crate::Subtest`]s, that do not carry an explicit number.
+    ///
+    /// With [`Strictness::Lenient`] the offending tests are returned in document order. With [`Strictness::Strict`] the
+    /// first offending test yields [`TapError::MissingTestNumber`], holding its 1-based position in document order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::{Document, Strictness, TapError};
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..3\n",
+    ///     "ok 1 - foo()\n",
+    ///     "ok - bar()\n",
+    ///     "ok 3 - baz()\n",
+    /// );
+    /// let doc = Document::parse_from_str(content).expect("Parser error");
+    ///
+    /// let unnumbered = doc.check_explicit_numbers(Strictness::Lenient).unwrap();
+    /// assert_eq!(unnumbered.len(), 1);
+    /// assert_eq!(unnumbered[0].description.as_deref(), Some("bar()"));
+    ///
+    /// let err = doc.check_explicit_numbers(Strictness::Strict).unwrap_err();
+    /// assert!(matches!(err, TapError::MissingTestNumber(2)));
+    /// ```
+    pub fn check_explicit_numbers(&self, strictness: Strictness) -> Result<Vec<&Test<'a>>> {
+        let unnumbered: Vec<_> = self
+            .tests()
+            .enumerate()
+            .filter(|(_, t)| t.number.is_none())
+            .map(|(index, t)| (index + 1, t))
+            .collect();
+        match (strictness, unnumbered.first()) {
+            (Strictness::Strict, Some((position, _))) => {
+                Err(TapError::MissingTestNumber(*position))
+            }
+            _ => Ok(unnumbered.into_iter().map(|(_, t)| t).collect()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec};
+
+    use super::*;
+
+    #[test]
+    fn test_check_explicit_numbers_nested() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..2\n",
+            "# Subtest: sub\n",
+            "    1..2\n",
+            "    ok 1\n",
+            "    ok\n",
+            "ok 2\n",
+        );
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        assert_eq!(
+            doc.check_explicit_numbers(Strictness::Lenient)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(matches!(
+            doc.check_explicit_numbers(Strictness::Strict),
+            Err(TapError::MissingTestNumber(2))
+        ));
+    }
+
+    #[test]
+    fn test_assert_matches_length() {
+        let content = concat!("TAP version 14\n", "1..2\n", "ok 1\n", "ok\n");
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        assert_eq!(
+            doc.assert_matches(&[(1, true), (2, true), (3, true)]),
+            Err(Mismatch {
+                index: 2,
+                expected: Some((3, true)),
+                actual: None
+            })
+        );
+        assert_eq!(
+            doc.assert_matches(&[(1, true)]),
+            Err(Mismatch {
+                index: 1,
+                expected: None,
+                actual: Some((2, true))
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate_plan_subrange() {
+        let content = concat!(
+            "TAP version 14\n",
+            "3..7\n",
+            "ok 3\n",
+            "ok 4\n",
+            "not ok 5\n",
+            "ok 6\n",
+            "ok 7\n",
+        );
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        assert_eq!((doc.plan.first, doc.plan.last, doc.plan.count()), (3, 7, 5));
+        assert!(doc.validate_plan().is_ok());
+        assert!(doc.check_numbering().is_empty());
+
+        let truncated = content.trim_end_matches("ok 7\n");
+        let doc = Document::parse_from_str(truncated).expect("Parser error");
+        let mismatch = doc.validate_plan().unwrap_err();
+        assert_eq!((mismatch.expected, mismatch.counted), (5, 4));
+        assert!(!mismatch.skip_all);
+    }
+
+    #[test]
+    fn test_validate_plan_subtest() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..1\n",
+            "# Subtest: outer\n",
+            "    1..1\n",
+            "    # Subtest: inner\n",
+            "        1..0\n",
+            "        ok 1\n",
+            "    ok 1 - inner\n",
+            "ok 1 - outer\n",
+        );
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        let mismatch = doc.validate_plan().unwrap_err();
+        assert_eq!(
+            mismatch,
+            PlanMismatch {
+                path: vec!["outer".to_owned(), "inner".to_owned()],
+                expected: 0,
+                counted: 1,
+                skip_all: true,
+            }
+        );
+        assert_eq!(
+            mismatch.to_string(),
+            "Plan of subtest 'outer/inner' skips all tests, but 1 appeared"
+        );
+    }
+
+    #[test]
+    fn test_check_numbering_subtest() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..2\n",
+            "# Subtest: sub\n",
+            "    1..5\n",
+            "    ok 1\n",
+            "    ok 4\n",
+            "    ok\n",
+            "ok 1 - sub\n",
+            "ok\n",
+        );
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        assert_eq!(
+            doc.check_numbering(),
+            [NumberingIssue {
+                path: vec!["sub".to_owned()],
+                kind: NumberingIssueKind::Gap { first: 2, last: 3 },
+            }]
+        );
+        assert_eq!(
+            doc.check_numbering()[0].to_string(),
+            "Tests 2 to 3 are missing in subtest 'sub'"
+        );
+    }
+
+    #[test]
+    fn test_check_numbering_sequential() {
+        for example in ["common", "move_mount_set_group_test", "yaml"] {
+            let contents = std::fs::read_to_string(format!("examples/{}.tap", example))
+                .expect("Failed to read file");
+            let doc = Document::parse_from_str(&contents).expect("Parser error");
+            assert_eq!(doc.check_numbering(), [], "{}", example);
+        }
+    }
+
+    #[test]
+    fn test_check_explicit_numbers_all_numbered() {
+        let content = concat!("TAP version 14\n", "1..2\n", "ok 1\n", "not ok 2\n");
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        assert!(doc
+            .check_explicit_numbers(Strictness::Strict)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,132 @@
+//! Visitor-based traversal of documents, for building several reports from one pass.
+
+use crate::{BailOut, Document, Pragma, Statement, Subtest, Test};
+
+/// Callbacks for each kind of [`Statement`] encountered while traversing a [`Document`] with [`Document::accept`].
+///
+/// All methods default to no-ops, so a visitor only implements the callbacks it cares about.
+pub trait Visitor {
+    /// Called for each [`Test`].
+    fn visit_test(&mut self, _test: &Test<'_>) {}
+
+    /// Called for each [`Subtest`], before the statements of its body are visited.
+    fn visit_subtest(&mut self, _subtest: &Subtest<'_>) {}
+
+    /// Called for each [`BailOut`].
+    fn visit_bail_out(&mut self, _bail_out: &BailOut<'_>) {}
+
+    /// Called for each [`Pragma`].
+    fn visit_pragma(&mut self, _pragma: &Pragma<'_>) {}
+
+    /// Called for each [`Statement::Comment`], with the comment text.
+    fn visit_comment(&mut self, _comment: &str) {}
+
+    /// Called for each [`Statement::Anything`], with the unrecognized text.
+    fn visit_anything(&mut self, _anything: &str) {}
+}
+
+/// Visit `statements` in order, descending into subtests.
+fn walk<V: Visitor + ?Sized>(statements: &[Statement], visitor: &mut V) {
+    for statement in statements {
+        match statement {
+            Statement::Test(test) => visitor.visit_test(test),
+            Statement::Subtest(subtest) => {
+                visitor.visit_subtest(subtest);
+                walk(&subtest.body, visitor);
+            }
+            Statement::BailOut(bail_out) => visitor.visit_bail_out(bail_out),
+            Statement::Pragma(pragma) => visitor.visit_pragma(pragma),
+            Statement::Comment(comment) => visitor.visit_comment(comment),
+            Statement::Anything(anything) => visitor.visit_anything(anything),
+        }
+    }
+}
+
+impl Document<'_> {
+    /// Traverse the document body in order, calling the matching [`Visitor`] method for each statement.
+    ///
+    /// Subtests are descended into depth-first, right after [`Visitor::visit_subtest`] is called for them. Thus, like
+    /// with [`Document::tests`], the tests of a subtest are visited before the test line closing the subtest.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::{Document, Subtest, Test, Visitor};
+    ///
+    /// #[derive(Default)]
+    /// struct Names(Vec<String>);
+    ///
+    /// impl Visitor for Names {
+    ///     fn visit_test(&mut self, test: &Test<'_>) {
+    ///         self.0.extend(test.description.as_deref().map(str::to_owned));
+    ///     }
+    ///
+    ///     fn visit_subtest(&mut self, subtest: &Subtest<'_>) {
+    ///         self.0.extend(subtest.name.map(|name| format!("# {}", name)));
+    ///     }
+    /// }
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..2\n",
+    ///     "# Subtest: sub\n",
+    ///     "    1..1\n",
+    ///     "    ok 1 - inner\n",
+    ///     "ok 1 - sub\n",
+    ///     "ok 2 - outer\n",
+    /// );
+    /// let doc = Document::parse_from_str(content).expect("Parser error");
+    /// let mut names = Names::default();
+    /// doc.accept(&mut names);
+    /// assert_eq!(names.0, ["# sub", "inner", "sub", "outer"]);
+    /// ```
+    pub fn accept<V: Visitor + ?Sized>(&self, visitor: &mut V) {
+        walk(&self.body, visitor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Key;
+
+    /// Counts failing tests, not counting TODO failures, and bail-outs.
+    #[derive(Default)]
+    struct FailureCounter {
+        failures: usize,
+        bail_outs: usize,
+    }
+
+    impl Visitor for FailureCounter {
+        fn visit_test(&mut self, test: &Test<'_>) {
+            if !test.result && !matches!(&test.directive, Some(d) if d.key == Key::Todo) {
+                self.failures += 1;
+            }
+        }
+
+        fn visit_bail_out(&mut self, _bail_out: &BailOut<'_>) {
+            self.bail_outs += 1;
+        }
+    }
+
+    #[test]
+    fn test_failure_counter() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..3\n",
+            "# Subtest: sub\n",
+            "    1..2\n",
+            "    not ok 1 - inner\n",
+            "    not ok 2 - later # TODO\n",
+            "not ok 1 - sub\n",
+            "ok 2\n",
+            "not ok 3\n",
+            "Bail out! boom\n",
+        );
+        let doc = Document::parse_from_str(content).expect("Parser error");
+        let mut counter = FailureCounter::default();
+        doc.accept(&mut counter);
+        assert_eq!(counter.failures, 3);
+        assert_eq!(counter.bail_outs, 1);
+    }
+}
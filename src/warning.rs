@@ -0,0 +1,274 @@
+//! Non-fatal findings reported alongside a successfully parsed [`Document`].
+
+use alloc::{
+    borrow::ToOwned,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{self, Display, Formatter};
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::{
+    Directive, Document, Key, NumberingIssueKind, ParseOptions, Plan, PlanMismatch, Result, Span,
+    Statement, Test,
+};
+
+/// The category of a [`Warning`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "snake_case"))]
+#[non_exhaustive]
+pub enum WarningCategory {
+    /// The number of tests in a plan scope doesn't match its [`Plan`], see [`Document::validate_plan`].
+    PlanMismatch,
+    /// A test number is lower than the number of the preceding test, see [`Document::check_numbering`].
+    OutOfOrderNumber,
+    /// A test number was already used by an earlier test, see [`Document::check_numbering`].
+    DuplicateNumber,
+    /// A test number doesn't fit an `i32`, see [`Test::has_invalid_number`].
+    InvalidNumber,
+    /// A directive other than `SKIP` or `TODO`, see [`Key::Other`].
+    UnknownDirective,
+    /// A line that isn't TAP, while `pragma +strict` is active.
+    NonTapLine,
+    /// A merged document declares another TAP version than the merged result, see [`Document::merge`].
+    VersionConflict,
+}
+
+/// A quality issue found by [`Document::parse_with_warnings`], which doesn't prevent the document from being parsed, or
+/// by [`Document::merge`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Warning {
+    /// What kind of issue was found.
+    pub category: WarningCategory,
+    /// Location of the offending construct. Plan mismatches point at the whole [`Document`] or
+    /// [`crate::Subtest`] the plan belongs to.
+    pub span: Span,
+    /// Human readable description of the issue.
+    pub message: String,
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}: {}", self.span.start_line, self.message)
+    }
+}
+
+/// Collects [`Warning`]s while walking the statements of a [`Document`].
+struct Scanner<'c> {
+    content: &'c str,
+    /// Byte offsets of the content's lines, see [`Scanner::span_of`].
+    line_starts: Option<Vec<usize>>,
+    warnings: Vec<Warning>,
+}
+
+impl Scanner<'_> {
+    fn warn(&mut self, category: WarningCategory, span: Span, message: String) {
+        self.warnings.push(Warning {
+            category,
+            span,
+            message,
+        });
+    }
+
+    /// Check the plan scope made up of `statements`, located at `span`, then recurse into nested subtests. Each
+    /// subtest starts out non-strict, like [`ParseOptions::pragmas`] treats it.
+    fn scan(&mut self, plan: &Plan, statements: &[Statement], span: Span, path: &mut Vec<String>) {
+        let expected = plan.count();
+        let counted = statements
+            .iter()
+            .filter(|s| matches!(s, Statement::Test(_)))
+            .count();
+        if expected != counted {
+            let mismatch = PlanMismatch {
+                path: path.clone(),
+                expected,
+                counted,
+                skip_all: plan.is_skip_all(),
+            };
+            self.warn(WarningCategory::PlanMismatch, span, mismatch.to_string());
+        }
+
+        let mut strict = false;
+        for statement in statements {
+            match statement {
+                Statement::Pragma(p) if p.option == "strict" => strict = p.flag != Some(false),
+                Statement::Anything(line) if strict => {
+                    let span = self.span_of(line);
+                    self.warn(
+                        WarningCategory::NonTapLine,
+                        span,
+                        "Line is not TAP while strict mode is active".to_owned(),
+                    );
+                }
+                Statement::Test(test) => self.scan_test(test),
+                Statement::Subtest(subtest) => {
+                    path.push(subtest.name.unwrap_or_default().to_owned());
+                    self.scan(&subtest.plan, &subtest.body, subtest.span, path);
+                    path.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn scan_test(&mut self, test: &Test) {
+        if let Some(Directive {
+            key: Key::Other(key),
+            ..
+        }) = &test.directive
+        {
+            let message = format!("Directive '{}' is neither SKIP nor TODO", key);
+            self.warn(WarningCategory::UnknownDirective, test.span, message);
+        }
+        if test.has_invalid_number() {
+            let message = format!(
+                "Test number '{}' is out of range",
+                test.raw_number.unwrap_or_default()
+            );
+            self.warn(WarningCategory::InvalidNumber, test.span, message);
+        }
+    }
+
+    /// Locate `line`, a slice of the parsed content. The offsets of the content's lines are collected on first use, so
+    /// locating many lines doesn't rescan the content for each of them.
+    fn span_of(&mut self, line: &str) -> Span {
+        let content = self.content;
+        let start = (line.as_ptr() as usize).saturating_sub(content.as_ptr() as usize);
+        let line_starts = self.line_starts.get_or_insert_with(|| {
+            core::iter::once(0)
+                .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+                .collect()
+        });
+        let index = line_starts.partition_point(|&s| s <= start);
+        let line_start = line_starts[index - 1];
+        pest::Span::new(content, start, start + line.len())
+            .zip(content.get(line_start..start))
+            .map(|(span, prefix)| Span::with_start(span, (index, prefix.chars().count() + 1)))
+            .unwrap_or_default()
+    }
+}
+
+impl<'a> Document<'a> {
+    /// Parse [`Document`] like [`Document::parse_from_str_with_options`], additionally reporting quality issues that
+    /// don't prevent parsing as [`Warning`]s, e.g. for a linter.
+    ///
+    /// Reported are plan mismatches, out-of-order, duplicate and out-of-range test numbers, unknown directives and,
+    /// while `pragma +strict` is active, non-TAP lines. Violations of `strict` are always reported as warnings, even
+    /// if [`ParseOptions::pragmas`] is enabled, so they don't fail parsing. Warnings are ordered by their location.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tapconsooomer::{Document, ParseOptions, WarningCategory};
+    ///
+    /// let content = concat!(
+    ///     "TAP version 14\n",
+    ///     "1..3\n",
+    ///     "ok 2 - foo()\n",
+    ///     "ok 1 - bar() # XPASS\n",
+    ///     "pragma +strict\n",
+    ///     "garbage\n",
+    /// );
+    /// let (doc, warnings) =
+    ///     Document::parse_with_warnings(content, &ParseOptions::new()).expect("Parser error");
+    /// assert_eq!(doc.tests().count(), 2);
+    ///
+    /// let categories: Vec<_> = warnings.iter().map(|w| (w.category, w.span.start_line)).collect();
+    /// assert_eq!(
+    ///     categories,
+    ///     [
+    ///         (WarningCategory::PlanMismatch, 1),
+    ///         (WarningCategory::UnknownDirective, 4),
+    ///         (WarningCategory::OutOfOrderNumber, 4),
+    ///         (WarningCategory::NonTapLine, 6),
+    ///     ]
+    /// );
+    /// assert_eq!(warnings[0].to_string(), "Line 1: Plan expects 3 tests, but 2 appeared");
+    /// ```
+    pub fn parse_with_warnings(
+        content: &'a str,
+        options: &ParseOptions,
+    ) -> Result<(Self, Vec<Warning>)> {
+        let options = options.clone().pragmas(false);
+        let document = Self::parse_from_str_with_options(content, &options)?;
+        let mut scanner = Scanner {
+            content,
+            line_starts: None,
+            warnings: Vec::new(),
+        };
+        scanner.scan(
+            &document.plan,
+            &document.body,
+            document.span,
+            &mut Vec::new(),
+        );
+        for issue in document.check_numbering() {
+            let (category, span) = match issue.kind {
+                NumberingIssueKind::OutOfOrder { span, .. } => {
+                    (WarningCategory::OutOfOrderNumber, span)
+                }
+                NumberingIssueKind::Duplicate { span, .. } => {
+                    (WarningCategory::DuplicateNumber, span)
+                }
+                /* Gaps have no location of their own and surface as plan mismatches anyway. */
+                NumberingIssueKind::Gap { .. } => continue,
+            };
+            scanner.warn(category, span, issue.to_string());
+        }
+        let mut warnings = scanner.warnings;
+        warnings.sort_by_key(|w| w.span.start);
+        Ok((document, warnings))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warnings_in_subtests() {
+        let content = concat!(
+            "TAP version 14\n",
+            "1..1\n",
+            "pragma +strict\n",
+            "# Subtest: inner\n",
+            "    garbage ignored, subtests start out non-strict\n",
+            "    pragma +strict\n",
+            "    ok 1\n",
+            "    ok 1 # SKIP duplicate\n",
+            "    ok 99999999999\n",
+            "    more garbage\n",
+            "    1..2\n",
+            "ok 1 - inner\n",
+        );
+        let options = ParseOptions::new().pragmas(true);
+        assert!(Document::parse_from_str_with_options(content, &options).is_err());
+
+        let (_, warnings) = Document::parse_with_warnings(content, &options).expect("Parser error");
+        let found: Vec<_> = warnings
+            .iter()
+            .map(|w| (w.category, w.span.start_line))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (WarningCategory::PlanMismatch, 4),
+                (WarningCategory::DuplicateNumber, 8),
+                (WarningCategory::InvalidNumber, 9),
+                (WarningCategory::NonTapLine, 10),
+            ]
+        );
+        assert_eq!(
+            warnings[0].message,
+            "Plan of subtest 'inner' expects 2 tests, but 3 appeared"
+        );
+        assert_eq!(warnings[3].span.start_col, 5);
+        let start = content.find("more garbage").unwrap();
+        let span = pest::Span::new(content, start, start + "more garbage".len()).unwrap();
+        assert_eq!(warnings[3].span, Span::from_pest(span));
+    }
+}
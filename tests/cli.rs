@@ -0,0 +1,212 @@
+#![cfg(feature = "cli")]
+
+use std::{
+    env, fs,
+    io::Write,
+    process::{self, Command, Stdio},
+};
+
+/// Run the `tap` binary with `args`, feeding `input` via stdin. Returns the exit code and stdout.
+fn tap(args: &[&str], input: &str) -> (Option<i32>, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_tap"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn tap");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .expect("Failed to write stdin");
+    let output = child.wait_with_output().expect("Failed to wait for tap");
+    (
+        output.status.code(),
+        String::from_utf8(output.stdout).expect("Invalid UTF-8 on stdout"),
+    )
+}
+
+#[test]
+fn test_pragma_json_is_clean() {
+    let input = concat!(
+        "TAP version 14\n",
+        "1..1\n",
+        "pragma +strict\n",
+        "ok 1 - foo()\n",
+        "pragma -strict\n",
+    );
+    let (code, stdout) = tap(&[], input);
+    assert_eq!(code, Some(0));
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("Invalid JSON on stdout");
+    assert_eq!(json["body"][0]["pragma"]["flag"], true);
+    assert_eq!(json["body"][2]["pragma"]["flag"], false);
+}
+
+#[test]
+fn test_multiple_files_are_ordered_by_path() {
+    let dir = env::temp_dir().join(format!("tap-cli-{}", process::id()));
+    fs::create_dir_all(&dir).expect("Failed to create directory");
+    fs::write(dir.join("b.tap"), "TAP version 14\n1..1\nnot ok 1 - b\n").unwrap();
+    fs::write(dir.join("a.tap"), "TAP version 14\n1..1\nok 1 - a\n").unwrap();
+    let pattern = dir.join("*.tap");
+
+    let (code, stdout) = tap(&[pattern.to_str().unwrap()], "");
+    fs::remove_dir_all(&dir).expect("Failed to remove directory");
+    assert_eq!(code, Some(2));
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("Invalid JSON on stdout");
+    let descriptions: Vec<_> = json
+        .as_array()
+        .expect("Expected an array of documents")
+        .iter()
+        .map(|doc| doc["body"][0]["test"]["description"].as_str().unwrap())
+        .collect();
+    assert_eq!(descriptions, ["a", "b"]);
+}
+
+#[test]
+fn test_existing_file_is_not_globbed() {
+    let dir = env::temp_dir().join(format!("tap-cli-literal-{}", process::id()));
+    fs::create_dir_all(&dir).expect("Failed to create directory");
+    fs::write(
+        dir.join("results[1].tap"),
+        "TAP version 14\n1..1\nok 1 - literal\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("results1.tap"),
+        "TAP version 14\n1..1\nok 1 - globbed\n",
+    )
+    .unwrap();
+
+    let (code, stdout) = tap(&[dir.join("results[1].tap").to_str().unwrap()], "");
+    let (_, globbed) = tap(&[dir.join("results[0-9].tap").to_str().unwrap()], "");
+    fs::remove_dir_all(&dir).expect("Failed to remove directory");
+    assert_eq!(code, Some(0));
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("Invalid JSON on stdout");
+    assert_eq!(json["body"][0]["test"]["description"], "literal");
+    /* A pattern still yields an array of documents, here matching `results1.tap` only. */
+    let json: serde_json::Value = serde_json::from_str(&globbed).expect("Invalid JSON on stdout");
+    assert_eq!(json.as_array().map(Vec::len), Some(1));
+    assert_eq!(json[0]["body"][0]["test"]["description"], "globbed");
+}
+
+#[test]
+fn test_failed_only() {
+    let input = concat!(
+        "TAP version 14\n",
+        "1..2\n",
+        "# Subtest: sub\n",
+        "    1..2\n",
+        "    ok 1 - fine\n",
+        "    not ok 2 - broken\n",
+        "not ok 1 - sub\n",
+        "ok 2 - fine\n",
+    );
+    let (code, stdout) = tap(&["--failed-only"], input);
+    assert_eq!(code, Some(2));
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("Invalid JSON on stdout");
+    assert_eq!(json["plan"]["last"], 2);
+    let body = json["body"].as_array().unwrap();
+    assert_eq!(body.len(), 2);
+    let inner = body[0]["subtest"]["body"].as_array().unwrap();
+    assert_eq!(inner.len(), 1);
+    assert_eq!(inner[0]["test"]["description"], "broken");
+    assert_eq!(body[1]["test"]["description"], "sub");
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_gzip_input_is_detected() {
+    use flate2::{write::GzEncoder, Compression};
+
+    let dir = env::temp_dir().join(format!("tap-cli-gzip-{}", process::id()));
+    fs::create_dir_all(&dir).expect("Failed to create directory");
+    let path = dir.join("results.tap.gz");
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(b"TAP version 14\n1..1\nnot ok 1 - packed\n")
+        .unwrap();
+    fs::write(&path, encoder.finish().unwrap()).unwrap();
+
+    let (code, stdout) = tap(&[path.to_str().unwrap()], "");
+    let (forced, _) = tap(&["--gzip"], "TAP version 14\n1..1\nok 1\n");
+    fs::remove_dir_all(&dir).expect("Failed to remove directory");
+    assert_eq!(code, Some(2));
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("Invalid JSON on stdout");
+    assert_eq!(json["body"][0]["test"]["description"], "packed");
+    assert_eq!(forced, Some(1));
+}
+
+#[test]
+fn test_omit_null() {
+    let input = "TAP version 14\n1..1\nok\n";
+    let (code, stdout) = tap(&["--omit-null"], input);
+    assert_eq!(code, Some(0));
+    assert!(!stdout.contains("null"), "{}", stdout);
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("Invalid JSON on stdout");
+    assert_eq!(json["body"][0]["test"]["effective_number"], 1);
+}
+
+#[test]
+fn test_yaml_as_object_toml() {
+    let input = fs::read_to_string("examples/yaml.tap").expect("Failed to read file");
+    let (code, stdout) = tap(&["-f", "toml", "--yaml-as-object"], &input);
+    assert_eq!(code, Some(2));
+    let toml: toml::Table = stdout.parse().expect("Invalid TOML on stdout");
+    /* `address: ~` is left out, as TOML can't represent null. */
+    let yaml = &toml["body"][0]["test"]["yaml"];
+    assert_eq!(yaml["found"].as_table().map(|t| t.len()), Some(1));
+    assert!(yaml["wanted"].get("address").is_some());
+
+    let (_, stdout) = tap(&["--yaml-as-object"], &input);
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("Invalid JSON on stdout");
+    let found = &json["body"][0]["test"]["yaml"]["found"];
+    assert_eq!(found.get("address"), Some(&serde_json::Value::Null));
+}
+
+#[test]
+fn test_summary_ends_like_prove() {
+    let (code, stdout) = tap(
+        &["--summary"],
+        "TAP version 14\n1..2\nok 1\nnot ok 2 # TODO\n",
+    );
+    assert_eq!(code, Some(0));
+    assert_eq!(stdout.lines().last(), Some("Result: PASS"));
+
+    let dir = env::temp_dir().join(format!("tap-cli-prove-{}", process::id()));
+    fs::create_dir_all(&dir).expect("Failed to create directory");
+    fs::write(dir.join("a.tap"), "TAP version 14\n1..2\nok 1\nnot ok 2\n").unwrap();
+    fs::write(dir.join("b.tap"), "TAP version 14\n1..1\nnot ok 1 # TODO\n").unwrap();
+    let pattern = dir.join("*.tap");
+
+    let (code, stdout) = tap(&["--summary", pattern.to_str().unwrap()], "");
+    let (strict, strict_stdout) = tap(
+        &["--summary", "--strict-exit", pattern.to_str().unwrap()],
+        "",
+    );
+    fs::remove_dir_all(&dir).expect("Failed to remove directory");
+    assert_eq!(code, Some(2));
+    let footer: Vec<_> = stdout.lines().rev().take(2).collect();
+    assert_eq!(footer, ["Result: FAIL (1 of 3)", "Files=2, Tests=3"]);
+    assert_eq!(strict, Some(2));
+    assert_eq!(strict_stdout.lines().last(), Some("Result: FAIL (2 of 3)"));
+}
+
+#[test]
+fn test_parse_error_names_file() {
+    let dir = env::temp_dir().join(format!("tap-cli-error-{}", process::id()));
+    fs::create_dir_all(&dir).expect("Failed to create directory");
+    fs::write(dir.join("a.tap"), "TAP version 14\n1..1\nok 1\n").unwrap();
+    fs::write(dir.join("b.tap"), "not TAP at all\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_tap"))
+        .args(["--color", "never", dir.join("*.tap").to_str().unwrap()])
+        .output()
+        .expect("Failed to run tap");
+    fs::remove_dir_all(&dir).expect("Failed to remove directory");
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).expect("Invalid UTF-8 on stderr");
+    assert!(stderr.contains("b.tap"), "{}", stderr);
+    assert!(!stderr.contains("a.tap"), "{}", stderr);
+}